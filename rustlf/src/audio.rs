@@ -1,6 +1,38 @@
-use std::{ffi::{c_char, c_void, CStr, CString}, sync::Arc};
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    fs::File,
+    io::BufReader,
+    sync::{Arc, Mutex},
+};
 
-use crate::{netkeyer::{NETKEYER, Netkeyer}, foreground::BACKGROUND_HANDLE, workqueue::WorkSender, background_process::BackgroundContext};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use crate::{
+    background_process::BackgroundContext,
+    err_utils::{log_message, LogLevel},
+    foreground::BACKGROUND_HANDLE,
+    netkeyer::{Netkeyer, NETKEYER},
+    workqueue::WorkSender,
+};
+
+/// Name of the output device a contester picked, or `None` for the host default.
+static OUTPUT_DEVICE: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("no matching output device")]
+    NoDevice,
+    #[error("could not read device configuration: {0}")]
+    DeviceConfig(#[from] cpal::DefaultStreamConfigError),
+    #[error("could not build output stream: {0}")]
+    BuildStream(#[from] cpal::BuildStreamError),
+    #[error("could not start output stream: {0}")]
+    PlayStream(#[from] cpal::PlayStreamError),
+    #[error("could not decode wav file: {0}")]
+    Wav(#[from] hound::Error),
+    #[error("could not open audio file: {0}")]
+    Io(#[from] std::io::Error),
+}
 
 struct PlaySoundConfig {
     pub(crate) netkeyer: Option<Arc<Netkeyer>>,
@@ -8,6 +40,109 @@ struct PlaySoundConfig {
     pub(crate) audiofile: CString,
 }
 
+/// A running playback. Dropping this stops the cpal stream and frees the decoded samples.
+struct Playback {
+    stream: cpal::Stream,
+}
+
+struct SampleSource {
+    samples: Vec<f32>,
+    channels: u16,
+    position: usize,
+}
+
+impl SampleSource {
+    fn from_wav(path: &CStr) -> Result<(SampleSource, cpal::SampleRate), Error> {
+        let path = path.to_string_lossy();
+        let reader = hound::WavReader::new(BufReader::new(File::open(&*path)?))?;
+        let spec = reader.spec();
+
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => {
+                reader.into_samples::<f32>().collect::<Result<_, _>>()?
+            }
+            hound::SampleFormat::Int => {
+                let max = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 / max))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+
+        Ok((
+            SampleSource {
+                samples,
+                channels: spec.channels,
+                position: 0,
+            },
+            cpal::SampleRate(spec.sample_rate),
+        ))
+    }
+
+    /// Fill `out` (interleaved, `out_channels` wide) from the source, up/down-mixing
+    /// channel count and padding with silence once the source is exhausted.
+    fn fill(&mut self, out: &mut [f32], out_channels: u16) {
+        for frame in out.chunks_mut(out_channels as usize) {
+            for (i, sample) in frame.iter_mut().enumerate() {
+                let src_channel = (i as u16 % self.channels) as usize;
+                *sample = self
+                    .samples
+                    .get(self.position + src_channel)
+                    .copied()
+                    .unwrap_or(0.0);
+            }
+            if self.position < self.samples.len() {
+                self.position += self.channels as usize;
+            }
+        }
+    }
+}
+
+fn output_device() -> Result<cpal::Device, Error> {
+    let host = cpal::default_host();
+    let wanted = OUTPUT_DEVICE.lock().unwrap().clone();
+
+    match wanted {
+        Some(name) => host
+            .output_devices()
+            .map_err(|_| Error::NoDevice)?
+            .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+            .ok_or(Error::NoDevice),
+        None => host.default_output_device().ok_or(Error::NoDevice),
+    }
+}
+
+impl Playback {
+    fn start(audiofile: &CStr) -> Result<Playback, Error> {
+        let (mut source, file_rate) = SampleSource::from_wav(audiofile)?;
+        let device = output_device()?;
+
+        let mut supported = device.default_output_config()?;
+        // Prefer a config matching the file's sample rate if the device offers one.
+        if let Ok(mut configs) = device.supported_output_configs() {
+            if let Some(range) = configs.find(|c| c.min_sample_rate() <= file_rate && file_rate <= c.max_sample_rate())
+            {
+                supported = range.with_sample_rate(file_rate);
+            }
+        }
+
+        let config: cpal::StreamConfig = supported.into();
+        let out_channels = config.channels;
+
+        let stream = device.build_output_stream(
+            &config,
+            move |data: &mut [f32], _| source.fill(data, out_channels),
+            |err| log_message!(LogLevel::WARN, format!("playback stream error: {err}")),
+            None,
+        )?;
+
+        stream.play()?;
+
+        Ok(Playback { stream })
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn prepare_playsound(audiofile: *const c_char) -> *mut c_void {
     let netkeyer = NETKEYER.with_borrow(|fg_netkeyer| fg_netkeyer.clone());
@@ -24,13 +159,21 @@ pub unsafe extern "C" fn prepare_playsound(audiofile: *const c_char) -> *mut c_v
     Box::into_raw(config) as *mut c_void
 }
 
+/// Stop a running playback returned by `init_playsound`, dropping the cpal stream
+/// and the decoded samples. The pointer is always treated as a playback handle, so
+/// a config from `prepare_playsound` is not accepted here.
 #[no_mangle]
-pub unsafe extern "C" fn abort_playsound(config: *mut c_void) {
-    std::mem::drop(Box::from_raw(config as *mut PlaySoundConfig));
+pub unsafe extern "C" fn abort_playsound(playback: *mut c_void) {
+    if !playback.is_null() {
+        std::mem::drop(Box::from_raw(playback as *mut Playback));
+    }
 }
 
+/// Consumes the config from `prepare_playsound`, decodes `audiofile` and starts
+/// streaming it to the configured output device. Returns an opaque handle to pass
+/// to `abort_playsound`/`close_playsound`, or null on failure.
 #[no_mangle]
-pub unsafe extern "C" fn init_playsound(config: *mut c_void) -> *mut c_char {
+pub unsafe extern "C" fn init_playsound(config: *mut c_void) -> *mut c_void {
     let PlaySoundConfig {
         netkeyer,
         bg_thread,
@@ -39,10 +182,68 @@ pub unsafe extern "C" fn init_playsound(config: *mut c_void) -> *mut c_char {
     NETKEYER.with_borrow_mut(|audio_netkeyer| *audio_netkeyer = netkeyer);
     BACKGROUND_HANDLE.with_borrow_mut(|audio_bg| *audio_bg = bg_thread);
 
-    audiofile.into_raw()
+    match Playback::start(&audiofile) {
+        Ok(playback) => Box::into_raw(Box::new(playback)) as *mut c_void,
+        Err(e) => {
+            log_message!(LogLevel::WARN, format!("Could not play {audiofile:?}: {e}"));
+            std::ptr::null_mut()
+        }
+    }
 }
 
+/// Called once playback has finished naturally; equivalent to `abort_playsound`.
 #[no_mangle]
-pub unsafe extern "C" fn close_playsound(audiofile: *mut c_char) {
-    std::mem::drop(CString::from_raw(audiofile));
+pub unsafe extern "C" fn close_playsound(playback: *mut c_void) {
+    abort_playsound(playback);
+}
+
+#[no_mangle]
+pub extern "C" fn playsound_device_count() -> usize {
+    let host = cpal::default_host();
+    host.output_devices()
+        .map(|devices| devices.count())
+        .unwrap_or(0)
+}
+
+/// Copies the name of the `index`th output device into `buffer` (which must be at
+/// least `len` bytes), truncating if necessary. Returns `false` if `index` is out
+/// of range.
+#[no_mangle]
+pub unsafe extern "C" fn playsound_device_name(index: usize, buffer: *mut c_char, len: usize) -> bool {
+    let host = cpal::default_host();
+    let Ok(mut devices) = host.output_devices() else {
+        return false;
+    };
+    let Some(Ok(name)) = devices.nth(index).map(|d| d.name()) else {
+        return false;
+    };
+
+    if len == 0 {
+        return false;
+    }
+
+    let name = CString::new(name).unwrap_or_default();
+    let bytes = name.as_bytes_with_nul();
+    let copy_len = bytes.len().min(len);
+    std::ptr::copy_nonoverlapping(bytes.as_ptr() as *const c_char, buffer, copy_len);
+    if copy_len == len {
+        *buffer.add(len - 1) = 0;
+    }
+    true
+}
+
+/// Selects the output device for the voice keyer by name; pass an empty string to
+/// go back to the host default.
+#[no_mangle]
+pub unsafe extern "C" fn playsound_set_device(name: *const c_char) -> bool {
+    let Ok(name) = CStr::from_ptr(name).to_str() else {
+        return false;
+    };
+
+    *OUTPUT_DEVICE.lock().unwrap() = if name.is_empty() {
+        None
+    } else {
+        Some(name.to_owned())
+    };
+    true
 }
@@ -2,14 +2,15 @@ use std::ffi::{c_int, c_void};
 use std::sync::{Arc, Condvar, Mutex};
 
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use cstr::cstr;
 
 use crate::cqww_simulator::CqwwSimulator;
-use crate::err_utils::{log_message, LogLevel};
+use crate::err_utils::{log_message, showmsg, LogLevel};
 use crate::foreground::{ForegroundContext, BACKGROUND_HANDLE, FOREGROUND_HANDLE};
-use crate::hamlib::Rig;
+use crate::hamlib::{apply_trxmode_outfreq, Rig, RigConfig};
+use crate::idle_actions::maybe_fire_idle;
 use crate::workqueue::{WorkSender, Worker};
 use crate::write_keyer::{write_keyer, KeyerConsumer};
 use newtlf::netkeyer::Netkeyer;
@@ -67,6 +68,36 @@ fn background_process_wait() -> bool {
     s.exit_request
 }
 
+/// Tracks exponential backoff between rig reconnect attempts after a
+/// transport-level error, so a USB/serial glitch doesn't peg the
+/// background thread retrying every tick.
+struct ReconnectState {
+    backoff: Duration,
+    next_attempt: Instant,
+}
+
+impl ReconnectState {
+    const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+    const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+    /// Starts a reconnect cycle with an immediate first attempt.
+    fn new() -> Self {
+        ReconnectState {
+            backoff: Self::INITIAL_BACKOFF,
+            next_attempt: Instant::now(),
+        }
+    }
+
+    /// Schedules the next attempt after the current backoff and doubles
+    /// it (capped) for the attempt after that.
+    fn backoff_again(self) -> Self {
+        ReconnectState {
+            backoff: (self.backoff * 2).min(Self::MAX_BACKOFF),
+            next_attempt: Instant::now() + self.backoff,
+        }
+    }
+}
+
 pub(crate) struct BackgroundConfig {
     pub(crate) keyer_consumer: KeyerConsumer,
     pub(crate) netkeyer: Option<Arc<Netkeyer>>,
@@ -88,10 +119,12 @@ unsafe fn background_process(config: BackgroundConfig) {
     let mut context = BackgroundContext {
         rig,
         simulator: CqwwSimulator::new(),
+        netkeyer: netkeyer.clone(),
     };
 
     let mut lantimesync: c_int = 0;
     let mut fldigi_rpc_cnt: bool = false;
+    let mut reconnect: Option<ReconnectState> = None;
 
     loop {
         if background_process_wait() {
@@ -133,15 +166,44 @@ unsafe fn background_process(config: BackgroundConfig) {
 
         if !is_background_process_stopped() {
             write_keyer(&mut keyer_consumer, context.rig.as_mut(), netkeyer.as_mut());
+            maybe_fire_idle();
         }
 
         tlf::handle_lan_recv(&mut lantimesync);
 
-        // get freq info from TRX
+        // get freq info from TRX, treating a transport-level error as
+        // recoverable: drop the link and let the reconnect logic below
+        // reopen it with backoff instead of leaving rig control dead for
+        // the rest of the session.
         if let Some(rig) = context.rig.as_mut() {
-            let _ = rig.poll().map_err(|e| {
-                log_message!(LogLevel::WARN, format!("Problem reading radio status: {e}"));
-            });
+            if let Err(e) = rig.poll() {
+                log_message!(
+                    LogLevel::WARN,
+                    format!("Problem reading radio status: {e}, will attempt to reconnect")
+                );
+                context.rig = None;
+                reconnect = Some(ReconnectState::new());
+            }
+        } else if unsafe { tlf::trx_control } {
+            if let Some(pending) = reconnect.take() {
+                if Instant::now() < pending.next_attempt {
+                    reconnect = Some(pending);
+                } else {
+                    showmsg!("Attempting to reconnect to rig...");
+                    match unsafe { RigConfig::from_globals() }.and_then(|config| config.open_rig()) {
+                        Ok(rig) => {
+                            showmsg!("Rig reconnected");
+                            context.rig = Some(rig);
+                            apply_trxmode_outfreq();
+                        }
+                        Err(e) => {
+                            let wait = pending.backoff;
+                            showmsg!(format!("Rig reconnect failed ({e}), retrying in {}s", wait.as_secs()));
+                            reconnect = Some(pending.backoff_again());
+                        }
+                    }
+                }
+            }
         }
     }
 }
@@ -149,6 +211,9 @@ unsafe fn background_process(config: BackgroundConfig) {
 pub(crate) struct BackgroundContext {
     pub(crate) rig: Option<Rig>,
     pub(crate) simulator: CqwwSimulator,
+    /// Clone of the keyer handle, so scheduled keyer commands (see
+    /// `crate::netkeyer::schedule_*`) can run off the foreground thread.
+    pub(crate) netkeyer: Option<Arc<Netkeyer>>,
 }
 
 pub(crate) fn with_background<F: FnOnce(&WorkSender<BackgroundContext>) -> T, T>(f: F) -> T {
@@ -11,8 +11,11 @@ use rand::{seq::SliceRandom, Rng};
 
 use crate::{
     background_process::{is_background_process_stopped, with_background},
+    cw_utils::GetCWSpeed,
+    err_utils::{log_message, LogLevel},
     netkeyer::write_tone,
     newtlf::countryfile::ffi::DXCC_DATA,
+    sidetone::SidetoneGenerator,
 };
 
 static CALLMASTER_RANDOM_LIST: OnceLock<Vec<CString>> = OnceLock::new();
@@ -25,6 +28,7 @@ pub struct CqwwSimulator {
     tonecpy: Option<c_int>,
     current_call: Option<&'static CStr>,
     repeat_count: usize,
+    sidetone: Option<SidetoneGenerator>,
 }
 
 impl Default for CqwwSimulator {
@@ -41,6 +45,7 @@ impl CqwwSimulator {
             tonecpy: None,
             current_call: None,
             repeat_count: 0,
+            sidetone: None,
         }
     }
 
@@ -55,12 +60,27 @@ impl CqwwSimulator {
                 .collect()
         });
 
+        match SidetoneGenerator::new() {
+            Ok(sidetone) => self.sidetone = Some(sidetone),
+            Err(e) => {
+                self.sidetone = None;
+                log_message!(LogLevel::INFO, format!("No local sidetone for simulator: {e}"));
+            }
+        }
+
         self.pick_call();
         self.enabled = true;
     }
 
     pub fn disable(&mut self) {
         self.enabled = false;
+        self.sidetone = None;
+    }
+
+    fn key_sidetone(&self, text: &CStr) {
+        if let Some(sidetone) = &self.sidetone {
+            sidetone.key(&text.to_string_lossy(), self.tone as u16, GetCWSpeed());
+        }
     }
 
     fn pick_call(&mut self) {
@@ -87,7 +107,9 @@ impl CqwwSimulator {
         self.set_tone();
         self.pick_call();
 
-        unsafe { tlf::sendmessage(self.current_call.unwrap().as_ptr()) };
+        let call = self.current_call.unwrap();
+        unsafe { tlf::sendmessage(call.as_ptr()) };
+        self.key_sidetone(call);
         self.repeat_count = 0;
         self.restore_tone();
     }
@@ -109,6 +131,7 @@ impl CqwwSimulator {
 
         let msg = CString::new(format!("TU 5NN {zone_str}")).unwrap();
         unsafe { tlf::sendmessage(msg.as_ptr()) };
+        self.key_sidetone(&msg);
         self.repeat_count = 0;
         self.restore_tone();
     }
@@ -126,6 +149,7 @@ impl CqwwSimulator {
 
         let msg = CString::new(msg).unwrap();
         unsafe { tlf::sendmessage(msg.as_ptr()) };
+        self.key_sidetone(&msg);
         self.restore_tone();
     }
 
@@ -1,6 +1,76 @@
-use std::ffi::{c_int, CStr, CString};
+use std::collections::VecDeque;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::sync::{Mutex, OnceLock};
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+
+/// Reference instant for turning `Instant::now()` readings into a
+/// comparable, storable offset (e.g. idle-timer bookkeeping), pinned to
+/// whichever caller reaches this first.
+pub(crate) fn process_start() -> Instant {
+    *PROCESS_START.get_or_init(Instant::now)
+}
+
+/// How many recent `showmsg!`/`shownr!`/`log_message!` lines to keep around
+/// for scrollback; the oldest line is dropped once a new one arrives at
+/// capacity.
+const MESSAGE_LOG_CAPACITY: usize = 200;
+
+struct LogEntry {
+    micros: u64,
+    message: CString,
+}
+
+static MESSAGE_LOG: OnceLock<Mutex<VecDeque<LogEntry>>> = OnceLock::new();
+
+fn message_log() -> &'static Mutex<VecDeque<LogEntry>> {
+    MESSAGE_LOG.get_or_init(|| Mutex::new(VecDeque::with_capacity(MESSAGE_LOG_CAPACITY)))
+}
+
+fn record_message(message: impl AsRef<CStr>) {
+    let mut log = message_log().lock().unwrap();
+    if log.len() >= MESSAGE_LOG_CAPACITY {
+        log.pop_front();
+    }
+    log.push_back(LogEntry {
+        micros: process_start().elapsed().as_micros() as u64,
+        message: message.as_ref().to_owned(),
+    });
+}
+
+/// Records `message` and passes it on to `tlf::showmsg` for the on-screen
+/// status line. Not meant to be called directly; use `showmsg!`.
+pub fn record_and_showmsg(message: &CStr) {
+    record_message(message);
+    unsafe { tlf::showmsg(message.as_ptr()) };
+}
+
+/// Records `"$message $nr"` and passes `message`/`nr` on to `tlf::shownr`
+/// for the on-screen status line. Not meant to be called directly; use
+/// `shownr!`.
+pub fn record_and_shownr(message: &CStr, nr: c_int) {
+    let line = CString::new(format!("{} {nr}", message.to_string_lossy())).expect("invalid message");
+    record_message(line);
+    unsafe { tlf::shownr(message.as_ptr(), nr) };
+}
+
+type MessageLogCallback = extern "C" fn(micros: u64, message: *const c_char, arg: *const c_void) -> bool;
+
+/// Dumps the buffered `showmsg!`/`shownr!`/`log_message!` lines, newest
+/// first, calling `callback` with each line's monotonic microsecond
+/// timestamp (measured from `process_start()`) and text; stops early if
+/// `callback` returns `true`.
+#[no_mangle]
+pub unsafe extern "C" fn message_log_dump(callback: MessageLogCallback, arg: *const c_void) {
+    let log = message_log().lock().unwrap();
+    for entry in log.iter().rev() {
+        if callback(entry.micros, entry.message.as_ptr(), arg) {
+            break;
+        }
+    }
+}
 
 pub enum LogLevel {
     DEBUG,
@@ -10,6 +80,8 @@ pub enum LogLevel {
 }
 
 pub fn log_message_raw(level: LogLevel, message: impl AsRef<CStr>) {
+    record_message(message.as_ref());
+
     unsafe {
         let lines = tlf::LINES;
         tlf::clear_line(lines - 1);
@@ -47,19 +119,19 @@ pub(crate) use log_message;
 
 macro_rules! showmsg {
     ($msg:literal) => {
-        unsafe { tlf::showmsg(cstr::cstr!($msg).as_ptr()) }
+        $crate::err_utils::record_and_showmsg(cstr::cstr!($msg))
     };
-    ($msg:expr) => {
+    ($msg:expr) => {{
         let s = std::ffi::CString::new($msg).expect("invalid message");
-        unsafe { tlf::showmsg(s.as_ptr()) }
-    };
+        $crate::err_utils::record_and_showmsg(&s)
+    }};
 }
 
 pub(crate) use showmsg;
 
 macro_rules! shownr {
     ($msg:literal, $nr:expr) => {
-        unsafe { tlf::shownr(cstr::cstr!($msg).as_ptr(), $nr) }
+        $crate::err_utils::record_and_shownr(cstr::cstr!($msg), $nr)
     };
 }
 
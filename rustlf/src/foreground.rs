@@ -3,9 +3,10 @@ use std::ffi::{c_char, c_int, c_uint, c_ulong, c_void};
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::background_process::BackgroundContext;
+use crate::background_process::{join_background_thread, BackgroundContext};
 use crate::err_utils::{showmsg, shownr};
-use crate::hamlib::{set_outfreq, Error, HamlibKeyer, Rig, RigConfig};
+use crate::hamlib::{apply_trxmode_outfreq, Error, HamlibKeyer, Rig, RigConfig};
+use crate::idle_actions::{fire_startup_action, record_activity};
 use crate::keyer_interface::{CwKeyerFrontend, NullKeyer};
 use crate::mfj1278::Mfj1278Keyer;
 use crate::netkeyer::{NetKeyerFrontend, Netkeyer, NETKEYER};
@@ -30,6 +31,12 @@ pub extern "C" fn foreground_init() -> *mut c_void {
     let (fg_producer, fg_worker) = workqueue::<ForegroundContext>(FOREGROUND_QUEUE_SIZE);
     BACKGROUND_HANDLE.with_borrow_mut(|bg| *bg = Some(bg_producer));
     FOREGROUND_WORKER.with_borrow_mut(|bg| *bg = Some(fg_worker));
+    // Also keep a sender on the foreground thread itself, so
+    // `schedule_foreground` can hand work back to `FOREGROUND_WORKER` even
+    // when called from the foreground thread (not just from the background
+    // thread, which gets its own copy of `fg_producer` via `BackgroundConfig`
+    // below).
+    FOREGROUND_HANDLE.with_borrow_mut(|fg| *fg = Some(fg_producer.clone()));
 
     let rig = unsafe { hamlib_init().ok() };
 
@@ -40,6 +47,8 @@ pub extern "C" fn foreground_init() -> *mut c_void {
     KEYER_INTERFACE.with_borrow_mut(|keyer| *keyer = Some(keyer_interface));
     NETKEYER.with_borrow_mut(|fg_netkeyer| *fg_netkeyer = netkeyer.clone());
 
+    fire_startup_action();
+
     fn assert_send<T: Send>() {}
     let _ = assert_send::<BackgroundConfig>;
     let bg_config = Box::new(BackgroundConfig {
@@ -52,6 +61,33 @@ pub extern "C" fn foreground_init() -> *mut c_void {
     Box::into_raw(bg_config) as *mut c_void
 }
 
+/// Counterpart to `foreground_init`/`spawn_background_thread`: stops any
+/// in-flight CW, signals the background thread to exit and joins it
+/// (dropping its `BackgroundContext` along the way, which closes the
+/// `Rig` cleanly via its `Drop` impl), then clears every thread-local
+/// `foreground_init` populated. `handle` is the pointer
+/// `spawn_background_thread` returned. Calling `foreground_init` again
+/// afterwards does not bring the runtime back up: the static keyer queue
+/// can only be split once (`keyer_queue_init` panics on a second call),
+/// and the background exit request stays set, so a newly spawned
+/// background thread would exit at once.
+#[no_mangle]
+pub unsafe extern "C" fn foreground_shutdown(handle: *mut c_void) {
+    KEYER_INTERFACE.with_borrow_mut(|keyer| {
+        if let Some(keyer) = keyer.as_mut() {
+            let _ = keyer.stop_keying();
+        }
+    });
+
+    unsafe { join_background_thread(handle) };
+
+    BACKGROUND_HANDLE.with_borrow_mut(|bg| *bg = None);
+    FOREGROUND_HANDLE.with_borrow_mut(|fg| *fg = None);
+    FOREGROUND_WORKER.with_borrow_mut(|fg| *fg = None);
+    KEYER_INTERFACE.with_borrow_mut(|keyer| *keyer = None);
+    NETKEYER.with_borrow_mut(|netkeyer| *netkeyer = None);
+}
+
 unsafe fn hamlib_init() -> Result<Rig, Error> {
     tlf::rig_set_debug(tlf::rig_debug_level_e_RIG_DEBUG_NONE);
 
@@ -82,12 +118,7 @@ unsafe fn hamlib_init() -> Result<Rig, Error> {
         }
     };
 
-    match tlf::trxmode as c_uint {
-        tlf::SSBMODE => set_outfreq(tlf::SETSSBMODE as _),
-        tlf::DIGIMODE => set_outfreq(tlf::SETDIGIMODE as _),
-        tlf::CWMODE => set_outfreq(tlf::SETCWMODE as _),
-        _ => (),
-    }
+    apply_trxmode_outfreq();
 
     Ok(rig)
 }
@@ -179,6 +210,7 @@ pub extern "C" fn getch_process() -> c_int {
         if let Some(err) = err {
             panic!("Recv error: {:?}", err);
         }
+        record_activity();
         c
     })
 }
@@ -202,6 +234,7 @@ pub extern "C" fn wgetch_process(w: *mut tlf::WINDOW) -> c_int {
         if let Some(err) = err {
             panic!("Recv error: {:?}", err);
         }
+        record_activity();
         c
     })
 }
@@ -220,6 +253,7 @@ pub unsafe extern "C" fn getnstr_process(buffer: *mut c_char, n: c_int) -> c_int
         if let Some(err) = err {
             panic!("Recv error: {:?}", err);
         }
+        record_activity();
         c
     })
 }
@@ -232,6 +266,18 @@ pub(crate) fn exec_foreground<F: FnOnce() + Send + 'static>(f: F) {
     }
 }
 
+/// Unconditionally hands `f` off to `FOREGROUND_WORKER` instead of running it
+/// inline, unlike `exec_foreground`. `in_foreground()` is true for the whole
+/// lifetime of the foreground/UI thread, not just while it's nested inside
+/// `Worker::process_blocking`/`process_until`'s dispatch loop, so
+/// `exec_foreground` would run `f` synchronously on the very thread that's
+/// meant to stay responsive. Use this for a closure that may block (e.g.
+/// waiting on a background-thread reply) when called from an FFI entry point
+/// the foreground thread calls directly.
+pub(crate) fn schedule_foreground<F: FnOnce() + Send + 'static>(f: F) {
+    with_foreground(|fg| fg.schedule_nowait(|_| f()).expect("send error"))
+}
+
 pub(crate) fn in_foreground() -> bool {
     FOREGROUND_WORKER.with_borrow(|fg| fg.is_some())
 }
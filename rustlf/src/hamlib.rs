@@ -10,25 +10,169 @@ use std::{
     time::{Duration, Instant},
 };
 
-use libc::{c_char, c_long};
+use libc::{c_char, c_float, c_long};
 use ptr::Unique;
 
 use crate::{
     background_process::{with_background, BackgroundContext},
     bands::freq2band,
     cw_utils::{GetCWSpeed, SetCWSpeed},
-    err_utils::{log_message, showmsg, shownr, LogLevel},
+    err_utils::{log_message, showmsg, shownr, CResult, LogLevel},
     workqueue::WorkSender,
 };
 
 const ENIMPL: c_int = -tlf::RIG_ENIMPL;
 const ENAVAIL: c_int = -tlf::RIG_ENAVAIL;
 
+/// Serial parity setting, mirrored onto Hamlib's `RIG_PARITY_*` constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum SerialParity {
+    #[default]
+    None,
+    Even,
+    Odd,
+}
+
+impl From<c_int> for SerialParity {
+    fn from(value: c_int) -> SerialParity {
+        match value {
+            1 => SerialParity::Even,
+            2 => SerialParity::Odd,
+            _ => SerialParity::None,
+        }
+    }
+}
+
+impl SerialParity {
+    fn as_hamlib(self) -> tlf::serial_parity_e {
+        match self {
+            SerialParity::None => tlf::serial_parity_e_RIG_PARITY_NONE,
+            SerialParity::Even => tlf::serial_parity_e_RIG_PARITY_EVEN,
+            SerialParity::Odd => tlf::serial_parity_e_RIG_PARITY_ODD,
+        }
+    }
+}
+
+/// Serial handshake setting, mirrored onto Hamlib's `RIG_HANDSHAKE_*` constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum SerialHandshake {
+    #[default]
+    None,
+    Hardware,
+    Software,
+}
+
+impl From<c_int> for SerialHandshake {
+    fn from(value: c_int) -> SerialHandshake {
+        match value {
+            1 => SerialHandshake::Hardware,
+            2 => SerialHandshake::Software,
+            _ => SerialHandshake::None,
+        }
+    }
+}
+
+impl SerialHandshake {
+    fn as_hamlib(self) -> tlf::serial_handshake_e {
+        match self {
+            SerialHandshake::None => tlf::serial_handshake_e_RIG_HANDSHAKE_NONE,
+            SerialHandshake::Hardware => tlf::serial_handshake_e_RIG_HANDSHAKE_HARDWARE,
+            SerialHandshake::Software => tlf::serial_handshake_e_RIG_HANDSHAKE_SOFTWARE,
+        }
+    }
+}
+
+/// Where PTT is actually asserted, mirrored onto Hamlib's `RIG_PTT_*`
+/// constants. `Rig` means over CAT, like the original `RIG_PTT_RIG`-only
+/// behavior; the others drive a dedicated line on `ptt_port` instead. `Rig`
+/// is also the fallback for an unset/unrecognized `ptt_type` config value
+/// (including the `0` every existing install has today), so upgrading
+/// without touching this new option reproduces the old always-on-if-CAT-
+/// capable behavior; `None` only applies when explicitly configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum PttType {
+    #[default]
+    Rig,
+    SerialRts,
+    SerialDtr,
+    Parallel,
+    None,
+}
+
+impl From<c_int> for PttType {
+    fn from(value: c_int) -> PttType {
+        match value {
+            2 => PttType::SerialRts,
+            3 => PttType::SerialDtr,
+            4 => PttType::Parallel,
+            5 => PttType::None,
+            _ => PttType::Rig,
+        }
+    }
+}
+
+impl PttType {
+    fn as_hamlib(self) -> tlf::ptt_type_t {
+        match self {
+            PttType::Rig => tlf::ptt_type_t_RIG_PTT_RIG,
+            PttType::SerialRts => tlf::ptt_type_t_RIG_PTT_SERIAL_RTS,
+            PttType::SerialDtr => tlf::ptt_type_t_RIG_PTT_SERIAL_DTR,
+            PttType::Parallel => tlf::ptt_type_t_RIG_PTT_PARALLEL,
+            PttType::None => tlf::ptt_type_t_RIG_PTT_NONE,
+        }
+    }
+}
+
+/// Where DCD is actually read from, mirrored onto Hamlib's `RIG_DCD_*`
+/// constants. Analogous to `PttType`, but for carrier detect on `dcd_port`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum DcdType {
+    Rig,
+    SerialCts,
+    SerialDsr,
+    Parallel,
+    #[default]
+    None,
+}
+
+impl From<c_int> for DcdType {
+    fn from(value: c_int) -> DcdType {
+        match value {
+            1 => DcdType::Rig,
+            2 => DcdType::SerialCts,
+            3 => DcdType::SerialDsr,
+            4 => DcdType::Parallel,
+            _ => DcdType::None,
+        }
+    }
+}
+
+impl DcdType {
+    fn as_hamlib(self) -> tlf::dcd_type_t {
+        match self {
+            DcdType::Rig => tlf::dcd_type_t_RIG_DCD_RIG,
+            DcdType::SerialCts => tlf::dcd_type_t_RIG_DCD_SERIAL_CTS,
+            DcdType::SerialDsr => tlf::dcd_type_t_RIG_DCD_SERIAL_DSR,
+            DcdType::Parallel => tlf::dcd_type_t_RIG_DCD_PARALLEL,
+            DcdType::None => tlf::dcd_type_t_RIG_DCD_NONE,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct RigConfig {
     model: tlf::rig_model_t,
     portname: Option<CString>,
     serial_rate: c_int,
+    data_bits: c_int,
+    stop_bits: c_int,
+    parity: SerialParity,
+    handshake: SerialHandshake,
+    ptt_port: Option<CString>,
+    ptt_type: PttType,
+    dcd_port: Option<CString>,
+    dcd_type: DcdType,
+    civaddr: Option<u8>,
     rigconf: Vec<(CString, CString)>,
     use_keyer: bool,
     cw_bandwidth: Option<tlf::pbwidth_t>,
@@ -44,9 +188,28 @@ struct RigState {
     mode: Option<tlf::rmode_t>,
     bandidx: Option<usize>,
     fldigi_carrier: Option<tlf::freq_t>,
+    strength: Option<c_int>,
+    swr: Option<f32>,
+    power: Option<f32>,
     time: Instant,
 }
 
+/// Latest meter readings from `RigState::poll`, cached so the main UI thread
+/// can read them without going through the background rig thread, the same
+/// way `USE_PTT` caches the PTT-capability check.
+#[derive(Default)]
+struct RigMeter {
+    strength: Option<c_int>,
+    swr: Option<f32>,
+    power: Option<f32>,
+}
+
+static RIG_METER: Mutex<RigMeter> = Mutex::new(RigMeter {
+    strength: None,
+    swr: None,
+    power: None,
+});
+
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum Error {
     #[error("hamlib return code: {0}")]
@@ -139,22 +302,9 @@ impl RigConfig {
     pub(crate) unsafe fn from_globals() -> Result<RigConfig, Error> {
         let model = tlf::myrig_model as tlf::rig_model_t;
 
-        let portname = if tlf::rigportname.is_null() {
-            None
-        } else {
-            let s = CStr::from_ptr(tlf::rigportname);
-            if s.to_bytes().is_empty() {
-                None
-            } else {
-                let mut s = s.to_owned().into_bytes();
-                // Remove final newline
-                if s.last() == Some(&b'\n') {
-                    s.pop();
-                }
-                Some(CString::new(s).unwrap())
-            }
-        };
-        // TODO: add a way to configure dcd and ptt, it is dead code in the original.
+        let portname = Self::parse_portname(tlf::rigportname);
+        let ptt_port = Self::parse_portname(tlf::pttportname);
+        let dcd_port = Self::parse_portname(tlf::dcdportname);
 
         let cw_bandwidth = Some(tlf::cw_bandwidth as c_long).filter(|b| *b > 0);
 
@@ -162,6 +312,15 @@ impl RigConfig {
             model,
             portname,
             serial_rate: tlf::serial_rate,
+            data_bits: tlf::serial_data_bits,
+            stop_bits: tlf::serial_stop_bits,
+            parity: tlf::serial_parity.into(),
+            handshake: tlf::serial_handshake.into(),
+            ptt_port,
+            ptt_type: tlf::ptt_type.into(),
+            dcd_port,
+            dcd_type: tlf::dcd_type.into(),
+            civaddr: Some(tlf::civaddr as u8).filter(|&a| a != 0),
             rigconf: RigConfig::parse_rigconf()?,
             use_keyer: tlf::cwkeyer == tlf::HAMLIB_KEYER as c_int,
             cw_bandwidth,
@@ -170,6 +329,25 @@ impl RigConfig {
         })
     }
 
+    /// Reads a `rc` file pathname global, stripping the trailing newline the
+    /// config reader leaves in and collapsing an empty value to `None`.
+    unsafe fn parse_portname(ptr: *const c_char) -> Option<CString> {
+        if ptr.is_null() {
+            return None;
+        }
+
+        let s = CStr::from_ptr(ptr);
+        if s.to_bytes().is_empty() {
+            return None;
+        }
+
+        let mut s = s.to_owned().into_bytes();
+        if s.last() == Some(&b'\n') {
+            s.pop();
+        }
+        Some(CString::new(s).unwrap())
+    }
+
     unsafe fn parse_rigconf() -> Result<Vec<(CString, CString)>, Error> {
         let rigconf = CStr::from_ptr(&tlf::rigconf as *const c_char)
             .to_str()
@@ -201,21 +379,25 @@ impl RigConfig {
             None => return Err(Error::InvalidModel),
         };
 
-        if let Some(ref portname) = self.portname {
-            assert!(portname.to_bytes_with_nul().len() < tlf::HAMLIB_FILPATHLEN as usize);
-            unsafe {
-                let rig = rig.as_mut();
-                libc::strncpy(
-                    &mut rig.state.rigport.pathname as *mut c_char,
-                    portname.as_ptr(),
-                    tlf::HAMLIB_FILPATHLEN as usize,
-                );
-            }
+        unsafe {
+            let rig = rig.as_mut();
+            copy_portname(&self.portname, &mut rig.state.rigport.pathname);
+            copy_portname(&self.ptt_port, &mut rig.state.pttport.pathname);
+            copy_portname(&self.dcd_port, &mut rig.state.dcdport.pathname);
+
+            rig.state.pttport.type_.ptt = self.ptt_type.as_hamlib();
+            rig.state.dcdport.type_.dcd = self.dcd_type.as_hamlib();
         }
 
         let caps = unsafe { &*rig.as_ref().caps };
-        /* If CAT PTT is wanted, test for CAT capability of rig backend. */
-        let has_ptt = caps.ptt_type == tlf::ptt_type_t_RIG_PTT_RIG;
+        /* If CAT PTT is wanted, test for CAT capability of rig backend; a
+         * dedicated PTT line (serial RTS/DTR, parallel) doesn't depend on
+         * backend support, since it bypasses CAT entirely. */
+        let has_ptt = match self.ptt_type {
+            PttType::Rig => caps.ptt_type == tlf::ptt_type_t_RIG_PTT_RIG,
+            PttType::None => false,
+            PttType::SerialRts | PttType::SerialDtr | PttType::Parallel => true,
+        };
 
         if self.want_ptt && !has_ptt {
             showmsg!("Controlling PTT via Hamlib is not supported for that rig!");
@@ -241,21 +423,17 @@ impl RigConfig {
         let rig_mut = unsafe { rig.handle.as_mut() };
 
         rig_mut.state.rigport.parm.serial.rate = self.serial_rate;
+        rig_mut.state.rigport.parm.serial.data_bits = self.data_bits;
+        rig_mut.state.rigport.parm.serial.stop_bits = self.stop_bits;
+        rig_mut.state.rigport.parm.serial.parity = self.parity.as_hamlib();
+        rig_mut.state.rigport.parm.serial.handshake = self.handshake.as_hamlib();
 
         for (param, value) in &self.rigconf {
-            unsafe {
-                let token = tlf::rig_token_lookup(rig_mut, param.as_ptr());
-                if token as c_uint == tlf::RIG_CONF_END {
-                    return Err(Error::InvalidRigconf);
-                }
-
-                let retval = tlf::rig_set_conf(rig_mut, token, value.as_ptr());
-                if retval != tlf::rig_errcode_e_RIG_OK as c_int {
-                    return Err(retval.into());
-                }
-            }
+            unsafe { set_conf_token(rig_mut, param, value) }?;
         }
 
+        self.apply_mfg_quirks(caps, rig_mut)?;
+
         let retval = unsafe { tlf::rig_open(rig.handle.as_mut()) };
         if retval != tlf::rig_errcode_e_RIG_OK as c_int {
             return Err(Error::Open(retval.into()));
@@ -289,6 +467,45 @@ impl RigConfig {
         }
         Ok(rig)
     }
+
+    /// Applies setup that can't be expressed as a generic `rigconf`
+    /// token=value pair because the user shouldn't have to know the raw
+    /// token syntax for it. Dispatches on the backend's manufacturer name so
+    /// further quirks can be added as new match arms without touching the
+    /// generic `rigconf` loop above.
+    fn apply_mfg_quirks(&self, caps: &tlf::rig_caps, rig_mut: *mut tlf::RIG) -> Result<(), Error> {
+        let mfg_name = unsafe { CStr::from_ptr(caps.mfg_name.as_ptr()) }.to_string_lossy();
+
+        match &*mfg_name {
+            "Icom" => {
+                if let Some(civaddr) = self.civaddr {
+                    let param = CString::new("civaddr").unwrap();
+                    let value = CString::new(format!("0x{civaddr:02x}")).unwrap();
+                    unsafe { set_conf_token(rig_mut, &param, &value) }?;
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+/// Looks up `param` as a Hamlib conf token for `rig_mut` and sets it to
+/// `value`, used both for user-supplied `rigconf` directives and for
+/// backend-specific auto-configuration (see `apply_mfg_quirks`).
+unsafe fn set_conf_token(rig_mut: *mut tlf::RIG, param: &CStr, value: &CStr) -> Result<(), Error> {
+    let token = tlf::rig_token_lookup(rig_mut, param.as_ptr());
+    if token as c_uint == tlf::RIG_CONF_END {
+        return Err(Error::InvalidRigconf);
+    }
+
+    let retval = tlf::rig_set_conf(rig_mut, token, value.as_ptr());
+    if retval != tlf::rig_errcode_e_RIG_OK as c_int {
+        return Err(retval.into());
+    }
+
+    Ok(())
 }
 
 impl Rig {
@@ -341,6 +558,29 @@ impl Rig {
         retval_to_result(retval)
     }
 
+    fn get_level_int(&mut self, level: tlf::setting_t) -> Result<c_int, GenericError> {
+        let mut value = MaybeUninit::uninit();
+
+        let retval = unsafe {
+            tlf::rig_get_level(self.handle.as_mut(), tlf::RIG_VFO_CURR, level, value.as_mut_ptr())
+        };
+        retval_to_result(retval).map(|_| unsafe { value.assume_init().i })
+    }
+
+    fn get_level_float(&mut self, level: tlf::setting_t) -> Result<f32, GenericError> {
+        let mut value = MaybeUninit::uninit();
+
+        let retval = unsafe {
+            tlf::rig_get_level(self.handle.as_mut(), tlf::RIG_VFO_CURR, level, value.as_mut_ptr())
+        };
+        retval_to_result(retval).map(|_| unsafe { value.assume_init().f })
+    }
+
+    fn has_get_level(&self, level: tlf::setting_t) -> bool {
+        let caps = unsafe { &*self.handle.as_ref().caps };
+        caps.has_get_level & level != 0
+    }
+
     fn stop_keyer(&mut self) -> Result<(), GenericError> {
         if !self.can_stop_morse {
             return Ok(());
@@ -450,6 +690,11 @@ impl Rig {
 
             self.set_band_mode(trxmode, state.mode, freq)?;
         }
+        *RIG_METER.lock().unwrap() = RigMeter {
+            strength: state.strength,
+            swr: state.swr,
+            power: state.power,
+        };
         self.state = Some(state);
         self.poll_keyer()?;
 
@@ -459,7 +704,10 @@ impl Rig {
     fn change_freq(&mut self, state: &RigState) -> Result<tlf::freq_t, Error> {
         // TODO: broadcast frequency properly from here
         let Some(freq) = state.freq else {
-            unsafe { tlf::freq = 0. };
+            // Leave `tlf::freq` (and the `RIG_METER` cache) at their last
+            // known value instead of clobbering them, so a transport
+            // glitch shows a stale-but-sane reading rather than zero
+            // while the background worker reconnects.
             return Err(Error::Poll);
         };
         let freq = radio_to_display_frequency(freq, Some(state));
@@ -552,6 +800,9 @@ impl RigState {
             bandwidth: None,
             fldigi_carrier: None,
             bandidx: None,
+            strength: None,
+            swr: None,
+            power: None,
         };
 
         // Initialize RIG_VFO_CURR
@@ -578,10 +829,36 @@ impl RigState {
         }
         out.bandidx = freq2band(radio_to_display_frequency(freq, Some(&out)) as c_uint);
 
+        if rig.has_get_level(tlf::RIG_LEVEL_STRENGTH) {
+            out.strength = rig.get_level_int(tlf::RIG_LEVEL_STRENGTH).ok();
+        }
+        if rig.has_get_level(tlf::RIG_LEVEL_SWR) {
+            out.swr = rig.get_level_float(tlf::RIG_LEVEL_SWR).ok();
+        }
+        if rig.has_get_level(tlf::RIG_LEVEL_RFPOWER_METER) {
+            out.power = rig.get_level_float(tlf::RIG_LEVEL_RFPOWER_METER).ok();
+        }
+
         out
     }
 }
 
+/// Copies `portname` into a fixed-size Hamlib port pathname buffer, if set.
+fn copy_portname(portname: &Option<CString>, dest: &mut [c_char; tlf::HAMLIB_FILPATHLEN as usize]) {
+    let Some(portname) = portname else {
+        return;
+    };
+    assert!(portname.to_bytes_with_nul().len() < tlf::HAMLIB_FILPATHLEN as usize);
+
+    unsafe {
+        libc::strncpy(
+            dest.as_mut_ptr(),
+            portname.as_ptr(),
+            tlf::HAMLIB_FILPATHLEN as usize,
+        );
+    }
+}
+
 fn get_ssb_mode(freq: tlf::freq_t) -> tlf::rmode_t {
     let freq = freq as c_uint;
     // LSB below 14 MHz, USB above it
@@ -601,6 +878,19 @@ fn with_rigerror<F: FnOnce(Cow<str>) -> T, T>(error: c_int, f: F) -> T {
     f(msg)
 }
 
+/// Re-applies the `set_outfreq` initialization for whichever trx mode is
+/// currently active, so a freshly opened rig (initial open or a reconnect)
+/// starts out on the contest's active SSB/DIGI/CW setup instead of
+/// whatever it powered up in.
+pub(crate) fn apply_trxmode_outfreq() {
+    match unsafe { tlf::trxmode } as c_uint {
+        tlf::SSBMODE => set_outfreq(tlf::SETSSBMODE as _),
+        tlf::DIGIMODE => set_outfreq(tlf::SETDIGIMODE as _),
+        tlf::CWMODE => set_outfreq(tlf::SETCWMODE as _),
+        _ => (),
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn set_outfreq(hertz: tlf::freq_t) {
     if !unsafe { tlf::trx_control } {
@@ -721,6 +1011,40 @@ pub unsafe extern "C" fn hamlib_set_ptt(ptt: bool) -> c_int {
     result_to_retval(ptt_result)
 }
 
+/// Returns the last-polled S-meter reading (relative dB, 0 = S9) through
+/// `out`, without blocking on the background rig thread. `CResult::Err` if
+/// the rig doesn't support reading it or no rig is open yet.
+#[no_mangle]
+pub unsafe extern "C" fn hamlib_get_smeter(out: *mut c_int) -> CResult {
+    let strength = RIG_METER.lock().unwrap().strength;
+    if let Some(strength) = strength {
+        *out = strength;
+    }
+    strength.into()
+}
+
+/// Returns the last-polled SWR reading through `out`, same caveats as
+/// `hamlib_get_smeter`.
+#[no_mangle]
+pub unsafe extern "C" fn hamlib_get_swr(out: *mut c_float) -> CResult {
+    let swr = RIG_METER.lock().unwrap().swr;
+    if let Some(swr) = swr {
+        *out = swr;
+    }
+    swr.into()
+}
+
+/// Returns the last-polled RF power meter reading through `out`, same
+/// caveats as `hamlib_get_smeter`.
+#[no_mangle]
+pub unsafe extern "C" fn hamlib_get_power(out: *mut c_float) -> CResult {
+    let power = RIG_METER.lock().unwrap().power;
+    if let Some(power) = power {
+        *out = power;
+    }
+    power.into()
+}
+
 fn print_error(e: GenericError) -> GenericError {
     log_message(LogLevel::WARN, format!("Problem with rig link: {e}"));
     e
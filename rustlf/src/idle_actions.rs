@@ -0,0 +1,108 @@
+//! Startup and idle keyer actions.
+//!
+//! A "startup" message is keyed once right after `keyer_init`; an "idle"
+//! message is keyed when the operator hasn't touched a key for a
+//! configurable interval (e.g. an auto-CQ). Both are just text fed into the
+//! same queue `keyer_append` feeds, so they key exactly like an operator-typed
+//! macro would. Idle detection piggybacks on the background worker's
+//! already-ticking loop rather than running its own timer: `foreground.rs`'s
+//! `getch_process`/`wgetch_process`/`getnstr_process` stamp every keypress via
+//! `record_activity`, and `maybe_fire_idle` (called once per background tick)
+//! compares against that stamp.
+
+use std::ffi::{c_char, c_uint, CStr, CString};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::err_utils::process_start;
+use crate::foreground::exec_foreground;
+use crate::write_keyer::keyer_append_safe;
+
+static STARTUP_MESSAGE: Mutex<Option<CString>> = Mutex::new(None);
+static IDLE_ACTION: Mutex<Option<IdleAction>> = Mutex::new(None);
+
+/// Set once an operator action (a keypress) is observed, so `maybe_fire_idle`
+/// only fires after the configured interval of genuine inactivity.
+static LAST_ACTIVITY_MICROS: AtomicU64 = AtomicU64::new(0);
+
+/// Set by the C side while a QSO exchange is in progress, so an auto-CQ
+/// doesn't key over an exchange that just isn't touching the keyboard.
+static OPERATOR_BUSY: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone)]
+struct IdleAction {
+    message: CString,
+    threshold: Duration,
+}
+
+fn micros_since_start() -> u64 {
+    process_start().elapsed().as_micros() as u64
+}
+
+/// Stamps the current time as the last operator activity, cancelling and
+/// rescheduling any pending idle fire. Called from `getch_process`,
+/// `wgetch_process`, and `getnstr_process` whenever they return a key.
+pub(crate) fn record_activity() {
+    LAST_ACTIVITY_MICROS.store(micros_since_start(), Ordering::Relaxed);
+}
+
+/// Configures the one-shot startup macro text; pass a null `text` to clear it.
+#[no_mangle]
+pub unsafe extern "C" fn set_startup_message(text: *const c_char) {
+    let text = (!text.is_null()).then(|| CStr::from_ptr(text).to_owned());
+    *STARTUP_MESSAGE.lock().unwrap() = text;
+}
+
+/// Configures the auto-idle macro text and its inactivity threshold; pass a
+/// null `text` to disable idle firing. Resets the activity timer so the
+/// idle interval is measured from the moment it's (re)configured, not from
+/// whatever the last keypress happened to be.
+#[no_mangle]
+pub unsafe extern "C" fn set_idle_message(text: *const c_char, idle_secs: c_uint) {
+    let action = (!text.is_null()).then(|| IdleAction {
+        message: CStr::from_ptr(text).to_owned(),
+        threshold: Duration::from_secs(idle_secs.into()),
+    });
+    *IDLE_ACTION.lock().unwrap() = action;
+    record_activity();
+}
+
+/// Lets the C side mark a defined "mid-exchange" state so the idle action
+/// doesn't step on an active QSO just because the operator isn't typing.
+#[no_mangle]
+pub extern "C" fn set_operator_busy(busy: bool) {
+    OPERATOR_BUSY.store(busy, Ordering::Relaxed);
+}
+
+/// Keys the configured startup message, if any. Called once from
+/// `foreground_init`, right after `keyer_init`.
+pub(crate) fn fire_startup_action() {
+    let Some(message) = STARTUP_MESSAGE.lock().unwrap().clone() else {
+        return;
+    };
+    exec_foreground(move || keyer_append_safe(message.as_bytes()));
+}
+
+/// Checks the configured idle threshold against the last recorded activity
+/// and keys the idle message once it's exceeded, resetting the timer so it
+/// doesn't refire every tick. Meant to be called once per background-worker
+/// tick; a no-op when no idle action is configured.
+pub(crate) fn maybe_fire_idle() {
+    if OPERATOR_BUSY.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let Some(action) = IDLE_ACTION.lock().unwrap().clone() else {
+        return;
+    };
+
+    let now = micros_since_start();
+    let elapsed = Duration::from_micros(now.saturating_sub(LAST_ACTIVITY_MICROS.load(Ordering::Relaxed)));
+    if elapsed < action.threshold {
+        return;
+    }
+
+    LAST_ACTIVITY_MICROS.store(now, Ordering::Relaxed);
+    exec_foreground(move || keyer_append_safe(action.message.as_bytes()));
+}
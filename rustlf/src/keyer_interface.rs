@@ -1,6 +1,7 @@
 use std::{
     ffi::{c_int, c_uint},
     ops::DerefMut,
+    time::Duration,
 };
 
 use crate::{
@@ -45,9 +46,22 @@ impl CwKeyerFrontend for NullKeyer {
 pub trait CwKeyerBackend {
     fn prepare_message(&self, _msg: &mut Vec<u8>) {}
 
+    /// Fire-and-forget send: queues `msg` with the backend and returns as
+    /// soon as it's been handed off, without waiting to see it through.
     fn send_message(&mut self, _msg: Vec<u8>) -> Result<(), Error> {
         Ok(())
     }
+
+    /// Confirming counterpart to `send_message`, for callers that need to
+    /// know the backend actually applied `msg` rather than merely queued
+    /// it. The default just forwards to `send_message` and reports
+    /// immediate success, since most backends have no notion of delivery
+    /// confirmation; backends that do (e.g. the cwdaemon netkeyer) override
+    /// this to retry and reconnect until confirmed or out of attempts,
+    /// waiting up to `timeout` for each attempt's confirmation.
+    fn send_message_confirmed(&mut self, msg: Vec<u8>, _timeout: Duration) -> Result<(), Error> {
+        self.send_message(msg)
+    }
 }
 
 pub(crate) fn with_keyer_interface<R, F: FnOnce(&mut dyn CwKeyerFrontend) -> R>(f: F) -> R {
@@ -11,11 +11,13 @@ pub mod err_utils;
 pub mod fldigi;
 mod foreground;
 mod hamlib;
+mod idle_actions;
 pub mod keyer_interface;
 pub mod mfj1278;
 mod netkeyer;
 pub mod newtlf;
 mod qtcutil;
+mod sidetone;
 pub mod weakstubs;
 pub mod workqueue;
 pub mod write_keyer;
@@ -1,13 +1,15 @@
 use std::cell::RefCell;
-use std::ffi::{c_char, c_int, c_uint, CStr};
+use std::ffi::{c_char, c_int, c_uint, c_void, CStr};
 use std::sync::atomic::{AtomicU16, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::background_process::{with_background, BackgroundContext};
 use crate::cw_utils::GetCWSpeed;
-use crate::err_utils::{log_message, CResult};
-use crate::foreground::exec_foreground;
+use crate::err_utils::{log_message, CResult, LogLevel};
+use crate::foreground::{exec_foreground, schedule_foreground};
 use crate::keyer_interface::{with_keyer_interface, CwKeyerBackend, CwKeyerFrontend};
-use newtlf::netkeyer::{Error, Netkeyer};
+use newtlf::netkeyer::{Cipher, Error, Netkeyer, ReplyStatus, RetryPolicy, TransportKind};
 
 thread_local! {
     pub(crate) static NETKEYER: RefCell<Option<Arc<Netkeyer>>> = RefCell::new(None);
@@ -21,11 +23,21 @@ static TONE: AtomicU16 = AtomicU16::new(DEFAULT_TONE);
 pub(crate) unsafe fn netkeyer_from_globals() -> Result<Netkeyer, Error> {
     let host = unsafe { CStr::from_ptr(&tlf::netkeyer_hostaddress as *const c_char) };
     let port = unsafe { tlf::netkeyer_port as c_uint }.try_into().unwrap();
-    let netkeyer =
-        Netkeyer::from_host_and_port(host.to_str().map_err(|_| Error::InvalidDevice)?, port)?;
+    let host = host.to_str().map_err(|_| Error::InvalidDevice)?;
 
-    netkeyer.reset()?;
-    netkeyer.set_weight(tlf::weight as i8)?;
+    let netkeyer = if tlf::netkeyer_remote {
+        let cipher_key = CStr::from_ptr(&tlf::netkeyer_cipher_key as *const c_char);
+        let cipher = if cipher_key.to_bytes().is_empty() {
+            Cipher::None
+        } else {
+            Cipher::Xor(cipher_key.to_bytes().to_vec())
+        };
+        Netkeyer::connect(TransportKind::Tcp, host, port, cipher)?
+    } else {
+        Netkeyer::from_host_and_port(host, port)?
+    };
+
+    netkeyer.configure(None, Some(tlf::weight as i8), None)?;
 
     netkeyer.write_tone(get_tone())?;
 
@@ -124,6 +136,37 @@ pub extern "C" fn netkeyer_set_band_switch(bandidx: c_uint) -> CResult {
     })
 }
 
+/// Blocking, confirming counterpart to `netkeyer_set_band_switch`: retries
+/// and reconnects per `RetryPolicy::default()`, and only returns
+/// `CResult::Ok` once cwdaemon's reply confirms the command was actually
+/// applied; `CResult::Err` once the retries are exhausted. Meant for
+/// critical commands where the caller needs to know the change landed,
+/// unlike the fire-and-forget `_async` commands.
+#[no_mangle]
+pub extern "C" fn netkeyer_set_band_switch_confirmed(bandidx: c_uint, timeout_ms: c_uint) -> CResult {
+    let Ok(bandidx) = bandidx.try_into() else {
+        return CResult::Err;
+    };
+    let timeout = Duration::from_millis(timeout_ms as u64);
+
+    with_netkeyer(|netkeyer| {
+        netkeyer.send_confirmed(&RetryPolicy::default(), timeout, move |netkeyer| {
+            netkeyer.set_band_switch(bandidx)
+        })
+    })
+}
+
+/// Blocking, confirming counterpart to `cwkeyer_reset`. See
+/// `netkeyer_set_band_switch_confirmed` for the retry/reconnect behavior.
+#[no_mangle]
+pub extern "C" fn netkeyer_reset_confirmed(timeout_ms: c_uint) -> CResult {
+    let timeout = Duration::from_millis(timeout_ms as u64);
+
+    with_netkeyer(|netkeyer| {
+        netkeyer.send_confirmed(&RetryPolicy::default(), timeout, |netkeyer| netkeyer.reset())
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn netkeyer_enable_word_mode() -> CResult {
     with_netkeyer(|netkeyer| netkeyer.enable_word_mode())
@@ -139,6 +182,94 @@ pub extern "C" fn netkeyer_set_sidetone_volume(volume: c_uint) -> CResult {
     })
 }
 
+fn send_with_retry<F: Fn(&Netkeyer) -> Result<(), Error>>(
+    netkeyer: &Netkeyer,
+    policy: &RetryPolicy,
+    f: F,
+) -> Result<(), Error> {
+    let mut delay = policy.initial_backoff;
+    let mut last_err = Error::InvalidDevice;
+
+    for attempt in 0..policy.attempts.max(1) {
+        match f(netkeyer) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                last_err = e;
+                if attempt + 1 < policy.attempts {
+                    std::thread::sleep(delay);
+                    delay *= policy.backoff_factor;
+                }
+            }
+        }
+    }
+
+    Err(last_err)
+}
+
+/// Schedules `f` to run against the background thread's netkeyer handle,
+/// retrying per `policy` on failure, without blocking the calling (foreground)
+/// thread. A final failure is logged once the command completes rather than
+/// falling back to SSB on the first transient error; success is not logged.
+///
+/// Reports the outcome via `schedule_foreground`, not `exec_foreground`: these
+/// `_async` FFI entry points are always called directly on the foreground
+/// thread, where `exec_foreground` would run the wait-then-log closure
+/// inline instead of handing it off, blocking the caller on the full
+/// retry/backoff cycle this function exists to avoid.
+fn schedule_netkeyer_command<F>(policy: RetryPolicy, f: F)
+where
+    F: Fn(&Netkeyer) -> Result<(), Error> + Send + 'static,
+{
+    let scheduled = with_background(|bg| {
+        bg.schedule_raw(move |ctx: &mut BackgroundContext| match &ctx.netkeyer {
+            Some(netkeyer) => send_with_retry(netkeyer, &policy, &f),
+            None => Err(Error::InvalidDevice),
+        })
+    });
+
+    let receiver = match scheduled {
+        Ok(receiver) => receiver,
+        Err(_) => {
+            log_message!(LogLevel::WARN, "Could not schedule keyer command");
+            return;
+        }
+    };
+
+    schedule_foreground(move || {
+        if let Ok(Err(e)) = receiver.recv() {
+            log_message!(LogLevel::WARN, format!("Keyer command failed: {e}"));
+        }
+    });
+}
+
+/// Async, auto-retrying counterpart to `netkeyer_set_ptt`/`netkeyer_set_band_switch`
+/// style calls: fires the speed change on the background thread and returns
+/// immediately, logging a failure once it's known.
+#[no_mangle]
+pub extern "C" fn netkeyer_set_speed_async(speed: c_uint) {
+    if let Ok(speed) = speed.try_into() {
+        schedule_netkeyer_command(RetryPolicy::default(), move |netkeyer| {
+            netkeyer.set_speed(speed)
+        });
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn netkeyer_set_weight_async(weight: c_int) {
+    if let Ok(weight) = weight.try_into() {
+        schedule_netkeyer_command(RetryPolicy::default(), move |netkeyer| {
+            netkeyer.set_weight(weight)
+        });
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn netkeyer_set_tone_async(tone: u16) {
+    schedule_netkeyer_command(RetryPolicy::default(), move |netkeyer| {
+        netkeyer.write_tone(tone)
+    });
+}
+
 fn with_netkeyer<R: Into<CResult>, F: FnOnce(&Netkeyer) -> R>(f: F) -> CResult {
     NETKEYER.with_borrow(|netkeyer| {
         if let Some(netkeyer) = netkeyer {
@@ -189,4 +320,67 @@ impl CwKeyerBackend for Arc<Netkeyer> {
     fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Error> {
         self.send_text(&msg)
     }
+
+    fn send_message_confirmed(&mut self, msg: Vec<u8>, timeout: Duration) -> Result<(), Error> {
+        self.send_confirmed(&RetryPolicy::default(), timeout, |netkeyer| netkeyer.send_text(&msg))
+    }
+}
+
+/// Wraps a C callback argument pointer so it can cross into the foreground
+/// closure below; the pointer itself is never dereferenced on this side.
+struct CallbackArg(*const c_void);
+unsafe impl Send for CallbackArg {}
+
+type KeyerReplyFn = extern "C" fn(confirmed: bool, callback_arg: *const c_void);
+
+/// Async send-and-confirm keying: queues `text` on the background thread's
+/// netkeyer and invokes `callback` once cwdaemon confirms everything queued
+/// up to and including it has been keyed, or once `timeout_ms` elapses
+/// without a reply. Mirrors the callback style of `callmaster_show_partials`.
+///
+/// Like `schedule_netkeyer_command`, the wait for the reply is handed off via
+/// `schedule_foreground` rather than `exec_foreground`: this is an FFI entry
+/// point called directly on the foreground thread, so `exec_foreground`
+/// would run the `reply.recv()` wait (up to `timeout_ms`) inline instead of
+/// deferring it, blocking the caller for exactly as long as this function is
+/// meant to avoid.
+#[no_mangle]
+pub unsafe extern "C" fn netkeyer_send_text_with_reply(
+    text: *const c_char,
+    timeout_ms: c_uint,
+    callback: KeyerReplyFn,
+    callback_arg: *const c_void,
+) {
+    let text = match CStr::from_ptr(text).to_str() {
+        Ok(text) => text.as_bytes().to_vec(),
+        Err(_) => return,
+    };
+    let timeout = Duration::from_millis(timeout_ms as u64);
+    let callback_arg = CallbackArg(callback_arg);
+
+    let scheduled = with_background(|bg| {
+        bg.schedule_raw(move |ctx: &mut BackgroundContext| match &ctx.netkeyer {
+            Some(netkeyer) => netkeyer.send_text_with_reply(&text, timeout).ok(),
+            None => None,
+        })
+    });
+
+    let receiver = match scheduled {
+        Ok(receiver) => receiver,
+        Err(_) => {
+            log_message!(LogLevel::WARN, "Could not schedule keyer command");
+            return;
+        }
+    };
+
+    schedule_foreground(move || {
+        let confirmed = receiver
+            .recv()
+            .ok()
+            .flatten()
+            .and_then(|reply| reply.recv().ok())
+            .is_some_and(|status| status == ReplyStatus::Confirmed);
+
+        callback(confirmed, callback_arg.0);
+    });
 }
@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeSet, HashMap},
     ffi::{c_char, CStr, CString},
     fs::File,
     io::Read,
@@ -12,7 +12,111 @@ use linereader::LineReader;
 
 use crate::err_utils::{log_message, LogLevel};
 
-pub struct CallMaster(BTreeSet<CString>);
+/// Trigram inverted index over a `CallMaster`'s calls, used to make
+/// `containing` (arbitrary substring search) sublinear. Maps every 3-byte
+/// window of each uppercased call to the sorted list of entry indices it
+/// appears in; `entries` is in the same ascending order as the `BTreeSet` it
+/// was built from, so candidate indices double as the final result order.
+struct TrigramIndex {
+    entries: Vec<CString>,
+    postings: HashMap<[u8; 3], Vec<u32>>,
+}
+
+impl TrigramIndex {
+    fn build(calls: &BTreeSet<CString>) -> Self {
+        let entries: Vec<CString> = calls.iter().cloned().collect();
+        let mut postings: HashMap<[u8; 3], Vec<u32>> = HashMap::new();
+
+        for (idx, call) in entries.iter().enumerate() {
+            for window in call.as_bytes().windows(3) {
+                let trigram: [u8; 3] = window.try_into().unwrap();
+                let postings = postings.entry(trigram).or_default();
+                if postings.last() != Some(&(idx as u32)) {
+                    postings.push(idx as u32);
+                }
+            }
+        }
+
+        TrigramIndex { entries, postings }
+    }
+
+    /// Intersects the posting lists of every trigram in `query`, returning
+    /// candidate entry indices in ascending order. `None` if `query` is
+    /// shorter than a trigram, so the caller can fall back to a full scan.
+    fn candidates(&self, query: &str) -> Option<Vec<u32>> {
+        let mut trigrams = query.as_bytes().windows(3).map(|window| {
+            let mut trigram = [0u8; 3];
+            trigram.copy_from_slice(window);
+            trigram
+        });
+
+        let mut candidates = self.postings.get(&trigrams.next()?).cloned().unwrap_or_default();
+
+        for trigram in trigrams {
+            let Some(postings) = self.postings.get(&trigram) else {
+                return Some(Vec::new());
+            };
+            candidates.retain(|idx| postings.binary_search(idx).is_ok());
+        }
+
+        Some(candidates)
+    }
+}
+
+/// A near-miss result from `CallMaster::within_edit_distance`. `distance`
+/// compares first, so the derived `Ord` sorts by ascending distance, then
+/// alphabetically by `call`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EditDistanceMatch<'a> {
+    pub distance: u8,
+    pub call: &'a CString,
+}
+
+/// Restricted (OSA) Damerau-Levenshtein distance between `a` and `b`,
+/// abandoned early and returning `None` as soon as either string length
+/// difference or a row's minimum cell exceeds `max`.
+fn damerau_levenshtein(a: &[u8], b: &[u8], max: u8) -> Option<u8> {
+    let max = max as usize;
+    let (n, m) = (a.len(), b.len());
+    if n.abs_diff(m) > max {
+        return None;
+    }
+
+    let mut prev2 = vec![0usize; m + 1];
+    let mut prev1: Vec<usize> = (0..=m).collect();
+
+    for i in 1..=n {
+        let mut curr = vec![0usize; m + 1];
+        curr[0] = i;
+        let mut row_min = curr[0];
+
+        for j in 1..=m {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut value = (prev1[j] + 1).min(curr[j - 1] + 1).min(prev1[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                value = value.min(prev2[j - 2] + 1);
+            }
+
+            curr[j] = value;
+            row_min = row_min.min(value);
+        }
+
+        if row_min > max {
+            return None;
+        }
+
+        prev2 = prev1;
+        prev1 = curr;
+    }
+
+    (prev1[m] <= max).then(|| prev1[m] as u8)
+}
+
+pub struct CallMaster {
+    calls: BTreeSet<CString>,
+    index: Option<TrigramIndex>,
+}
 
 impl CallMaster {
     pub fn parse<R: Read, C: FnMut(&str)>(
@@ -40,7 +144,7 @@ impl CallMaster {
         max_line_length: usize,
         only_na: bool,
     ) -> Result<Self, std::io::Error> {
-        let mut set = BTreeSet::new();
+        let mut calls = BTreeSet::new();
 
         Self::parse(reader, max_line_length, |call| {
             if only_na && !"AKWVCN".contains(call.chars().next().unwrap()) {
@@ -49,44 +153,85 @@ impl CallMaster {
             let mut call = call.to_owned();
             call.make_ascii_uppercase();
             if let Ok(call) = CString::new(call) {
-                set.insert(call);
+                calls.insert(call);
             }
         })?;
 
-        Ok(CallMaster(set))
+        let index = Some(TrigramIndex::build(&calls));
+        Ok(CallMaster { calls, index })
     }
 
     pub fn starting_with<'a>(&'a self, query: &'a CString) -> impl Iterator<Item = &CString> + 'a {
         // FIXME: find a way to feed a CStr to BTreeSet::range.
-        self.0
+        self.calls
             .range::<CString, RangeFrom<&CString>>(query..)
             .take_while(|&call| call.as_bytes().starts_with(query.to_bytes()))
     }
 
-    pub fn containing<'a>(&'a self, query: &'a CStr) -> impl Iterator<Item = &CString> + 'a {
-        let query = query.to_string_lossy();
+    pub fn containing<'a>(&'a self, query: &'a CStr) -> Box<dyn Iterator<Item = &CString> + 'a> {
+        let query = query.to_string_lossy().into_owned();
+
+        match self.index.as_ref().and_then(|index| index.candidates(&query).map(|c| (index, c))) {
+            Some((index, candidates)) => Box::new(
+                candidates
+                    .into_iter()
+                    .map(move |idx| &index.entries[idx as usize])
+                    .filter(move |call| {
+                        // Safety: all set calls must be valid UTF-8.
+                        let call = unsafe { std::str::from_utf8_unchecked(call.as_bytes()) };
+                        call.contains(&query)
+                    }),
+            ),
+            None => Box::new(self.calls.iter().filter(move |&call| {
+                // Safety: all set calls must be valid UTF-8.
+                let call = unsafe { std::str::from_utf8_unchecked(call.as_bytes()) };
+                call.contains(&query)
+            })),
+        }
+    }
+
+    /// Near-miss lookup for a mistyped or mis-heard callsign: every call
+    /// within Damerau-Levenshtein distance `max` of `query`, ranked by
+    /// ascending distance then alphabetically. Gated by length difference
+    /// so the scan only runs the DP table against calls that could
+    /// plausibly be within `max` edits.
+    pub fn within_edit_distance<'a>(
+        &'a self,
+        query: &CStr,
+        max: u8,
+    ) -> impl Iterator<Item = EditDistanceMatch<'a>> + 'a {
+        let query = query.to_bytes().to_ascii_uppercase();
 
-        self.0.iter().filter(move |&call| {
-            // Safety: all set calls must be valid UTF-8.
-            let call = unsafe { std::str::from_utf8_unchecked(call.as_bytes()) };
-            call.contains(&*query)
-        })
+        let mut matches: Vec<EditDistanceMatch<'a>> = self
+            .calls
+            .iter()
+            .filter(|call| call.as_bytes().len().abs_diff(query.len()) <= max as usize)
+            .filter_map(|call| {
+                damerau_levenshtein(call.as_bytes(), &query, max).map(|distance| EditDistanceMatch { distance, call })
+            })
+            .collect();
+
+        matches.sort();
+        matches.into_iter()
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.calls.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.calls.is_empty()
     }
 
     pub fn as_inner(&self) -> &BTreeSet<CString> {
-        &self.0
+        &self.calls
     }
 }
 
-pub static GLOBAL_CALLMASTER: RwLock<CallMaster> = RwLock::new(CallMaster(BTreeSet::new()));
+pub static GLOBAL_CALLMASTER: RwLock<CallMaster> = RwLock::new(CallMaster {
+    calls: BTreeSet::new(),
+    index: None,
+});
 
 #[no_mangle]
 pub unsafe extern "C" fn load_callmaster_inner(path: *const c_char, only_na: bool) -> usize {
@@ -132,17 +277,38 @@ pub unsafe extern "C" fn callmaster_show_partials(
     }
 }
 
+/// Near-miss counterpart to `callmaster_show_partials`, for offering
+/// correction hints on a callsign that doesn't match by prefix or
+/// substring: calls `callback` with every call within `max_distance`
+/// edits of `query`, ranked by ascending distance then alphabetically.
+#[no_mangle]
+pub unsafe extern "C" fn callmaster_show_near_misses(
+    query: *const c_char,
+    max_distance: u8,
+    callback: ShowPartialFn,
+    callback_arg: *const c_void,
+) {
+    let query = CStr::from_ptr(query);
+
+    let guard = GLOBAL_CALLMASTER.read().unwrap();
+    for found in guard.within_edit_distance(query, max_distance) {
+        if callback(found.call.as_ptr(), callback_arg) {
+            break;
+        }
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn callmaster_contains(query: *const c_char) -> bool {
     let query = CStr::from_ptr(query);
     let guard = GLOBAL_CALLMASTER.read().unwrap();
-    guard.0.contains(query)
+    guard.calls.contains(query)
 }
 
 #[no_mangle]
 pub extern "C" fn callmaster_len() -> usize {
     let guard = GLOBAL_CALLMASTER.read().unwrap();
-    guard.0.len()
+    guard.calls.len()
 }
 
 pub const CALLMASTER_VERSION_LEN: usize = 11;
@@ -162,3 +328,30 @@ pub unsafe extern "C" fn callmaster_version(buffer: *mut c_char) {
         buffer.write(0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damerau_levenshtein_equal() {
+        assert_eq!(damerau_levenshtein(b"W1AW", b"W1AW", 3), Some(0));
+    }
+
+    #[test]
+    fn test_damerau_levenshtein_transposition() {
+        // OSA transposition is a single edit, not two substitutions.
+        assert_eq!(damerau_levenshtein(b"W1AW", b"1WAW", 1), Some(1));
+    }
+
+    #[test]
+    fn test_damerau_levenshtein_insert_delete() {
+        assert_eq!(damerau_levenshtein(b"K1ABC", b"K1ABCD", 1), Some(1));
+        assert_eq!(damerau_levenshtein(b"K1ABCD", b"K1ABC", 1), Some(1));
+    }
+
+    #[test]
+    fn test_damerau_levenshtein_pruned_by_max() {
+        assert_eq!(damerau_levenshtein(b"W1AW", b"VE3XYZ", 2), None);
+    }
+}
@@ -7,6 +7,7 @@ use std::{
 
 use self::parser::{parse_reader, CountryLine, Line};
 
+pub(crate) mod cache;
 pub mod parser;
 
 const MAX_LINE_LENGTH: usize = 256;
@@ -162,16 +163,16 @@ impl CountryData {
         parse_reader(
             reader,
             |line: Result<_, _>| match line {
-                Ok((_, Line::Country(country))) => {
+                Ok(Line::Country(country)) => {
                     data.push_country(country);
                     Ok(())
                 }
-                Ok((_, Line::Prefixes(prefixes))) => {
+                Ok(Line::Prefixes(prefixes)) => {
                     data.push_prefixes(&prefixes);
                     Ok(())
                 }
-                Ok((_, Line::Empty)) => Ok(()),
-                Err(_) => Err(std::io::Error::from(std::io::ErrorKind::InvalidData)),
+                Ok(Line::Empty) => Ok(()),
+                Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
             },
             MAX_LINE_LENGTH,
         )?;
@@ -179,6 +180,42 @@ impl CountryData {
         Ok(data)
     }
 
+    /// Async counterpart to `load`, for streaming a `cty.dat` in over the
+    /// network instead of blocking a thread on a synchronous `Read`. Reuses
+    /// the exact same grammar via `parse_reader_async`, so the two loaders
+    /// can never drift apart.
+    #[cfg(feature = "async")]
+    pub async fn load_async<R: tokio::io::AsyncBufRead + Unpin>(
+        reader: R,
+    ) -> Result<CountryData, std::io::Error> {
+        let mut data = CountryData::default();
+
+        parser::parse_reader_async(
+            reader,
+            |line: Result<_, _>| {
+                let result = match line {
+                    Ok(Line::Country(country)) => {
+                        data.push_country(country);
+                        Ok(())
+                    }
+                    Ok(Line::Prefixes(prefixes)) => {
+                        data.push_prefixes(&prefixes);
+                        Ok(())
+                    }
+                    Ok(Line::Empty) => Ok(()),
+                    Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
+                };
+                // push_country/push_prefixes never actually await; this block
+                // only exists to satisfy parse_reader_async's async callback.
+                async move { result }
+            },
+            MAX_LINE_LENGTH,
+        )
+        .await?;
+
+        Ok(data)
+    }
+
     pub fn find_full_match(&self, call: &str) -> Option<usize> {
         self.prefix_map.get(call).copied()
     }
@@ -220,6 +257,167 @@ impl CountryData {
             Some(std::str::from_utf8(&self.version[..VERSION_LENGTH - 1]).unwrap())
         }
     }
+
+    /// Serializes this data to the compact binary cache format understood by
+    /// `from_cache`, tagged with `source` so a later load can tell whether the
+    /// `cty.dat` it was built from is still the one on disk.
+    pub fn to_cache(&self, source: cache::SourceInfo) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut cursor = cache::CursorMut::new(&mut buf);
+
+        cache::write_header(&mut cursor, &self.version, source);
+        cursor.put_usize(self.countries.len());
+        for country in &self.countries {
+            cache::write_country(&mut cursor, country);
+        }
+        cursor.put_usize(self.prefixes.len());
+        for prefix in &self.prefixes {
+            cache::write_prefix(&mut cursor, prefix);
+        }
+
+        buf
+    }
+
+    /// Loads data previously written by `to_cache`. Returns `Err` on any magic,
+    /// format-version mismatch, or if `source` doesn't match the `cty.dat` the
+    /// cache was derived from, in which case the caller should fall back to
+    /// `load` and re-emit a fresh cache with `to_cache`.
+    pub fn from_cache(bytes: &[u8], source: cache::SourceInfo) -> Result<CountryData, cache::Error> {
+        let mut cursor = cache::Cursor::new(bytes);
+        let version = cache::read_header(&mut cursor, source)?;
+
+        let mut data = CountryData {
+            version,
+            ..Default::default()
+        };
+
+        let country_count = cursor.get_usize()?;
+        data.countries.reserve(country_count);
+        for _ in 0..country_count {
+            data.countries.push(cache::read_country(&mut cursor)?);
+        }
+
+        let prefix_count = cursor.get_usize()?;
+        data.prefixes.reserve(prefix_count);
+        for _ in 0..prefix_count {
+            let prefix = cache::read_prefix(&mut cursor)?;
+            data.prefix_map
+                .insert(prefix.prefix.to_str().unwrap().to_owned(), data.prefixes.len());
+            data.prefixes.push(prefix);
+        }
+
+        Ok(data)
+    }
+
+    /// Computes the `SourceInfo` (length + CRC-32) a cache should be tagged
+    /// with for a given `cty.dat` byte slice.
+    pub fn source_info(cty_dat: &[u8]) -> cache::SourceInfo {
+        cache::SourceInfo {
+            len: cty_dat.len() as u32,
+            crc32: crc32fast::hash(cty_dat),
+        }
+    }
+
+    /// Writes the binary cache for this data to `path`, tagged with `source`.
+    pub fn save_cache(&self, path: impl AsRef<std::path::Path>, source: cache::SourceInfo) -> Result<(), cache::Error> {
+        std::fs::write(path, self.to_cache(source))?;
+        Ok(())
+    }
+
+    /// Loads the binary cache at `path`, rejecting it with `cache::Error::Stale`
+    /// if it wasn't derived from the `cty.dat` described by `source`.
+    pub fn load_cache(
+        path: impl AsRef<std::path::Path>,
+        source: cache::SourceInfo,
+    ) -> Result<CountryData, cache::Error> {
+        let bytes = std::fs::read(path)?;
+        CountryData::from_cache(&bytes, source)
+    }
+
+    /// Loads the `cty.dat` at `path`, preferring the binary cache sitting
+    /// alongside it (`path` with `.bin` appended) over re-running the nom
+    /// parser. Falls back to `load` and rewrites the cache on a cold start,
+    /// a format upgrade, or a `cty.dat` that's changed since the cache was
+    /// written; a failure to write the refreshed cache is not fatal, since
+    /// the freshly parsed data is still returned either way.
+    pub fn load_from_path(path: impl AsRef<std::path::Path>) -> Result<CountryData, std::io::Error> {
+        let path = path.as_ref();
+        let cty_dat = std::fs::read(path)?;
+        let source = CountryData::source_info(&cty_dat);
+
+        let cache_path = {
+            let mut p = path.as_os_str().to_owned();
+            p.push(".bin");
+            std::path::PathBuf::from(p)
+        };
+
+        if let Ok(data) = CountryData::load_cache(&cache_path, source) {
+            return Ok(data);
+        }
+
+        let data = CountryData::load::<(), _>(cty_dat.as_slice())?;
+        let _ = data.save_cache(&cache_path, source);
+
+        Ok(data)
+    }
+
+    /// Serializes this data back out in `cty.dat` text format via the
+    /// `Display` impls `parser::CountryLine`/`parser::Prefix` use to
+    /// reproduce the grammar `parser::line` accepts. Each prefix's
+    /// overrides are reconstructed by diffing its resolved fields against
+    /// its country's, since that's the only place the distinction between
+    /// "inherited from the country" and "explicit override" still lives
+    /// once a `cty.dat` has been parsed into `CountryData`.
+    #[allow(clippy::float_cmp)]
+    pub fn write_cty_dat<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+        let mut prefixes_by_country: Vec<Vec<&Prefix>> = vec![Vec::new(); self.countries.len()];
+        for prefix in &self.prefixes {
+            prefixes_by_country[prefix.country_idx].push(prefix);
+        }
+
+        for (country, prefixes) in self.countries.iter().zip(&prefixes_by_country) {
+            let country_line = parser::CountryLine {
+                main_prefix: country.main_prefix.to_str().unwrap(),
+                name: country.name.to_str().unwrap(),
+                cq_zone: country.cq_zone,
+                itu_zone: country.itu_zone,
+                continent: Continent::from_cstr(country.continent),
+                lat: country.lat,
+                lon: country.lon,
+                timezone: country.timezone,
+                starred: country.starred,
+            };
+            writeln!(w, "{country_line}")?;
+
+            // `parser::prefix_line` only accepts an indented prefix line.
+            if !prefixes.is_empty() {
+                write!(w, "    ")?;
+            }
+            for (i, prefix) in prefixes.iter().enumerate() {
+                let override_ = parser::PrefixOverrides {
+                    cq_zone: (prefix.cq_zone.0 != country.cq_zone.0).then_some(prefix.cq_zone),
+                    itu_zone: (prefix.itu_zone.0 != country.itu_zone.0).then_some(prefix.itu_zone),
+                    coordinates: ((prefix.lat, prefix.lon) != (country.lat, country.lon))
+                        .then_some((prefix.lat, prefix.lon)),
+                    continent: (prefix.continent != country.continent)
+                        .then(|| Continent::from_cstr(prefix.continent)),
+                    timezone: (prefix.timezone != country.timezone).then_some(prefix.timezone),
+                };
+                let parsed_prefix = parser::Prefix {
+                    exact: prefix.exact,
+                    prefix: prefix.prefix.to_str().unwrap(),
+                    override_,
+                };
+                let sep = if i + 1 == prefixes.len() { ';' } else { ',' };
+                write!(w, "{parsed_prefix}{sep}")?;
+            }
+            if !prefixes.is_empty() {
+                writeln!(w)?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[repr(u8)]
@@ -244,4 +442,21 @@ impl Continent {
             Continent::Oceania => cstr!(OC),
         }
     }
+
+    fn from_cstr(continent: &CStr) -> Self {
+        match continent.to_bytes() {
+            b"SA" => Continent::SouthAmerica,
+            b"EU" => Continent::Europe,
+            b"AS" => Continent::Asia,
+            b"AF" => Continent::Africa,
+            b"OC" => Continent::Oceania,
+            _ => Continent::NorthAmerica,
+        }
+    }
+}
+
+impl std::fmt::Display for Continent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_cstr().to_str().unwrap())
+    }
 }
@@ -0,0 +1,241 @@
+//! Compact binary cache for parsed cty.dat data, so startup doesn't have to
+//! re-run the text parser every time. The format is a magic+version header
+//! followed by the `countries` and `prefixes` vectors written back to back;
+//! `prefix_map` is never serialized and gets rebuilt on load.
+use std::ffi::{CStr, CString};
+
+use super::{Continent, Country, CqZone, ItuZone, Prefix, VERSION_LENGTH};
+
+const MAGIC: &[u8; 4] = b"TCTY";
+const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("cache truncated or corrupt")]
+    Truncated,
+    #[error("cache magic or format version mismatch")]
+    FormatMismatch,
+    #[error("cache was derived from a different cty.dat than the one on disk")]
+    Stale,
+    #[error("invalid string in cache")]
+    InvalidString,
+    #[error("invalid continent code in cache")]
+    InvalidContinent,
+    #[error("I/O error accessing cache file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Identifies the `cty.dat` a cache was built from, so a stale cache (the
+/// text file changed since) is detected without re-parsing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceInfo {
+    pub len: u32,
+    pub crc32: u32,
+}
+
+/// A read-only cursor over a byte slice, tracking an offset and returning
+/// `Error::Truncated` instead of panicking when a read runs past the end.
+pub(crate) struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Cursor { buf, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let slice = self.buf.get(self.pos..self.pos + len).ok_or(Error::Truncated)?;
+        self.pos += len;
+        Ok(slice)
+    }
+
+    pub(crate) fn get_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn get_bool(&mut self) -> Result<bool, Error> {
+        Ok(self.get_u8()? != 0)
+    }
+
+    pub(crate) fn get_u32_le(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn get_usize(&mut self) -> Result<usize, Error> {
+        Ok(self.get_u32_le()? as usize)
+    }
+
+    pub(crate) fn get_f32_le(&mut self) -> Result<f32, Error> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Reads a length-prefixed byte string (stored without its NUL
+    /// terminator) as previously written by `CursorMut::put_cstr`.
+    pub(crate) fn get_cstring(&mut self) -> Result<CString, Error> {
+        let len = self.get_usize()?;
+        let bytes = self.take(len)?;
+        CString::new(bytes.to_vec()).map_err(|_| Error::InvalidString)
+    }
+
+    pub(crate) fn get_exact(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        self.take(len)
+    }
+}
+
+/// The write side of `Cursor`, appending to a growable `Vec<u8>`.
+pub(crate) struct CursorMut<'a> {
+    buf: &'a mut Vec<u8>,
+}
+
+impl<'a> CursorMut<'a> {
+    pub(crate) fn new(buf: &'a mut Vec<u8>) -> Self {
+        CursorMut { buf }
+    }
+
+    pub(crate) fn put_u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    pub(crate) fn put_bool(&mut self, value: bool) {
+        self.put_u8(value as u8);
+    }
+
+    pub(crate) fn put_u32_le(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn put_usize(&mut self, value: usize) {
+        self.put_u32_le(value as u32);
+    }
+
+    pub(crate) fn put_f32_le(&mut self, value: f32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn put_cstr(&mut self, value: &CStr) {
+        let bytes = value.to_bytes();
+        self.put_usize(bytes.len());
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn put_bytes(&mut self, value: &[u8]) {
+        self.buf.extend_from_slice(value);
+    }
+}
+
+fn continent_from_u8(value: u8) -> Result<Continent, Error> {
+    Ok(match value {
+        0 => Continent::NorthAmerica,
+        1 => Continent::SouthAmerica,
+        2 => Continent::Europe,
+        3 => Continent::Asia,
+        4 => Continent::Africa,
+        5 => Continent::Oceania,
+        _ => return Err(Error::InvalidContinent),
+    })
+}
+
+fn continent_to_u8(continent: &CStr) -> u8 {
+    match continent.to_bytes() {
+        b"NA" => 0,
+        b"SA" => 1,
+        b"EU" => 2,
+        b"AS" => 3,
+        b"AF" => 4,
+        b"OC" => 5,
+        _ => 0,
+    }
+}
+
+pub(crate) fn write_country(cursor: &mut CursorMut, country: &Country) {
+    cursor.put_cstr(&country.main_prefix);
+    cursor.put_cstr(&country.name);
+    cursor.put_u8(country.cq_zone.0);
+    cursor.put_u8(country.itu_zone.0);
+    cursor.put_u8(continent_to_u8(country.continent));
+    cursor.put_f32_le(country.lat);
+    cursor.put_f32_le(country.lon);
+    cursor.put_f32_le(country.timezone);
+    cursor.put_bool(country.starred);
+}
+
+pub(crate) fn read_country(cursor: &mut Cursor) -> Result<Country, Error> {
+    Ok(Country {
+        main_prefix: cursor.get_cstring()?,
+        name: cursor.get_cstring()?,
+        cq_zone: CqZone(cursor.get_u8()?),
+        itu_zone: ItuZone(cursor.get_u8()?),
+        continent: continent_from_u8(cursor.get_u8()?)?.as_cstr(),
+        lat: cursor.get_f32_le()?,
+        lon: cursor.get_f32_le()?,
+        timezone: cursor.get_f32_le()?,
+        starred: cursor.get_bool()?,
+    })
+}
+
+pub(crate) fn write_prefix(cursor: &mut CursorMut, prefix: &Prefix) {
+    cursor.put_cstr(&prefix.prefix);
+    cursor.put_u8(prefix.cq_zone.0);
+    cursor.put_u8(prefix.itu_zone.0);
+    cursor.put_usize(prefix.country_idx);
+    cursor.put_f32_le(prefix.lat);
+    cursor.put_f32_le(prefix.lon);
+    cursor.put_u8(continent_to_u8(prefix.continent));
+    cursor.put_f32_le(prefix.timezone);
+    cursor.put_bool(prefix.exact);
+}
+
+pub(crate) fn read_prefix(cursor: &mut Cursor) -> Result<Prefix, Error> {
+    Ok(Prefix {
+        prefix: cursor.get_cstring()?,
+        cq_zone: CqZone(cursor.get_u8()?),
+        itu_zone: ItuZone(cursor.get_u8()?),
+        country_idx: cursor.get_usize()?,
+        lat: cursor.get_f32_le()?,
+        lon: cursor.get_f32_le()?,
+        continent: continent_from_u8(cursor.get_u8()?)?.as_cstr(),
+        timezone: cursor.get_f32_le()?,
+        exact: cursor.get_bool()?,
+    })
+}
+
+pub(crate) fn write_header(
+    cursor: &mut CursorMut,
+    version: &[u8; VERSION_LENGTH],
+    source: SourceInfo,
+) {
+    cursor.put_bytes(MAGIC);
+    cursor.put_u32_le(FORMAT_VERSION);
+    cursor.put_u32_le(source.len);
+    cursor.put_u32_le(source.crc32);
+    cursor.put_bytes(version);
+}
+
+/// Reads and validates the header, checking the cache's recorded source
+/// `len`/`crc32` against `expected_source` so a cache left over from a
+/// previous `cty.dat` is rejected as `Error::Stale` instead of silently
+/// returning stale prefix data.
+pub(crate) fn read_header(
+    cursor: &mut Cursor,
+    expected_source: SourceInfo,
+) -> Result<[u8; VERSION_LENGTH], Error> {
+    if cursor.get_exact(MAGIC.len())? != MAGIC {
+        return Err(Error::FormatMismatch);
+    }
+    if cursor.get_u32_le()? != FORMAT_VERSION {
+        return Err(Error::FormatMismatch);
+    }
+    let source = SourceInfo {
+        len: cursor.get_u32_le()?,
+        crc32: cursor.get_u32_le()?,
+    };
+    if source != expected_source {
+        return Err(Error::Stale);
+    }
+    cursor
+        .get_exact(VERSION_LENGTH)?
+        .try_into()
+        .map_err(|_| Error::Truncated)
+}
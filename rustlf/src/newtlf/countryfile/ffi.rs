@@ -1,28 +1,27 @@
 use std::{
     cell::UnsafeCell,
     ffi::{c_char, CStr},
-    fs::File,
     str::Utf8Error,
 };
 
-use crate::err_utils::CResult;
+use crate::err_utils::{log_message, CResult, LogLevel};
 
-use super::{dummy_country, dummy_prefix, Country, DxccData, Prefix};
+use super::{dummy_country, dummy_prefix, Country, CountryData, Prefix};
 
 // Safety: calling code expected to enforce synchronization
-struct GlobalDxccData(std::cell::UnsafeCell<Option<DxccData>>);
+struct GlobalCountryData(std::cell::UnsafeCell<Option<CountryData>>);
 
-unsafe impl Sync for GlobalDxccData {}
+unsafe impl Sync for GlobalCountryData {}
 
-static DXCC_DATA: GlobalDxccData = GlobalDxccData(UnsafeCell::new(None));
+static DXCC_DATA: GlobalCountryData = GlobalCountryData(UnsafeCell::new(None));
 
-impl GlobalDxccData {
-    unsafe fn get(&self) -> &DxccData {
+impl GlobalCountryData {
+    unsafe fn get(&self) -> &CountryData {
         let inner = &mut *self.0.get();
-        inner.as_ref().expect("GlobalDxccData not initialized")
+        inner.as_ref().expect("GlobalCountryData not initialized")
     }
 
-    unsafe fn get_mut(&self) -> &mut DxccData {
+    unsafe fn get_mut(&self) -> &mut CountryData {
         let inner = &mut *self.0.get();
         inner.get_or_insert_with(Default::default)
     }
@@ -125,10 +124,16 @@ pub unsafe extern "C" fn load_ctydata(path: *const c_char) -> CResult {
     let dd = unsafe { DXCC_DATA.get_mut() };
     let path = unsafe { ptr_to_str(path).map_err(|_| std::io::ErrorKind::InvalidData.into()) };
 
-    path.and_then(File::open).and_then(|file| {
-        DxccData::load::<std::io::Error, _>(file)
-    }).map(|data| {
-        *dd = data; Ok::<_, std::io::Error>(())
-    }).into()
+    let result = path.and_then(CountryData::load_from_path);
+
+    if let Err(e) = &result {
+        // `e`'s message already carries a `CtyParseError`'s "line N, col M:
+        // expected FIELD" detail when the failure was a bad cty.dat line
+        // (see `CountryData::load`); surface it here instead of discarding
+        // it into a bare ok/err `CResult`.
+        log_message!(LogLevel::WARN, format!("Could not load cty.dat: {e}"));
+    }
+
+    result.map(|data| *dd = data).into()
 }
 
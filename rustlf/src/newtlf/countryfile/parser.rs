@@ -18,7 +18,107 @@ use nom::{
     IResult, InputTakeAtPosition,
 };
 
-type PResult<'a, T> = IResult<&'a str, T>;
+/// Which field of a `cty.dat` line a parse failure happened in, used by
+/// `CtyParseError` to produce a human-readable diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    CountryName,
+    CqZone,
+    ItuZone,
+    Continent,
+    Latitude,
+    Longitude,
+    Timezone,
+    MainPrefix,
+    Delimiter(char),
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::CountryName => write!(f, "country name"),
+            Field::CqZone => write!(f, "CQ zone"),
+            Field::ItuZone => write!(f, "ITU zone"),
+            Field::Continent => write!(f, "continent"),
+            Field::Latitude => write!(f, "latitude"),
+            Field::Longitude => write!(f, "longitude"),
+            Field::Timezone => write!(f, "timezone"),
+            Field::MainPrefix => write!(f, "prefix"),
+            Field::Delimiter(c) => write!(f, "'{c}'"),
+        }
+    }
+}
+
+/// A `cty.dat` parse diagnostic: which line and column the parser gave up
+/// at, and which field it was expecting there.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("line {line_no}, col {column}: expected {expected}")]
+pub struct CtyParseError {
+    pub line_no: usize,
+    pub column: usize,
+    pub expected: Field,
+}
+
+/// nom error carried while parsing a single line: the remaining input at the
+/// point of failure (used to recover the column) and the deepest `Field`
+/// label a `field()`-wrapped combinator attached to it.
+#[derive(Debug, Clone)]
+struct RawError<'a> {
+    input: &'a str,
+    expected: Option<Field>,
+}
+
+impl<'a> nom::error::ParseError<&'a str> for RawError<'a> {
+    fn from_error_kind(input: &'a str, _kind: ErrorKind) -> Self {
+        RawError {
+            input,
+            expected: None,
+        }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+/// Wraps `parser`, labelling its failure with `expected` unless something
+/// deeper already claimed a more specific label.
+fn field<'a, O, F>(expected: Field, mut parser: F) -> impl FnMut(&'a str) -> PResult<'a, O>
+where
+    F: FnMut(&'a str) -> PResult<'a, O>,
+{
+    move |input: &'a str| {
+        parser(input).map_err(|err| {
+            err.map(|mut e: RawError<'a>| {
+                if e.expected.is_none() {
+                    e.expected = Some(expected);
+                }
+                e
+            })
+        })
+    }
+}
+
+/// Turns a failed single-line parse into a `CtyParseError` by recovering the
+/// column from the pointer distance between `line` and the error's
+/// remaining-input slice, and falling back to `Field::CountryName` (the
+/// first thing a line is parsed as) if nothing more specific was labelled.
+fn finalize_error(err: nom::Err<RawError>, line: &str, line_no: usize) -> CtyParseError {
+    let err = match err {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e,
+        nom::Err::Incomplete(_) => unreachable!("complete combinators never return Incomplete"),
+    };
+
+    let column = err.input.as_ptr() as usize - line.as_ptr() as usize + 1;
+
+    CtyParseError {
+        line_no,
+        column,
+        expected: err.expected.unwrap_or(Field::CountryName),
+    }
+}
+
+type PResult<'a, T> = IResult<&'a str, T, RawError<'a>>;
 
 #[derive(Debug)]
 pub enum Line<'a> {
@@ -64,19 +164,70 @@ pub struct PrefixOverrides {
     pub timezone: Option<f32>,
 }
 
+impl std::fmt::Display for CountryLine<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}:{}:{}:{}:{}{}:",
+            self.name,
+            u8::from(self.cq_zone),
+            u8::from(self.itu_zone),
+            self.continent,
+            self.lat,
+            self.lon,
+            self.timezone,
+            if self.starred { "*" } else { "" },
+            self.main_prefix,
+        )
+    }
+}
+
+impl std::fmt::Display for PrefixOverrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(cq_zone) = self.cq_zone {
+            write!(f, "({})", u8::from(cq_zone))?;
+        }
+        if let Some(itu_zone) = self.itu_zone {
+            write!(f, "[{}]", u8::from(itu_zone))?;
+        }
+        if let Some((lat, lon)) = self.coordinates {
+            write!(f, "<{lat}/{lon}>")?;
+        }
+        if let Some(continent) = &self.continent {
+            write!(f, "{{{continent}}}")?;
+        }
+        if let Some(timezone) = self.timezone {
+            write!(f, "~{timezone}~")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Prefix<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.exact {
+            write!(f, "=")?;
+        }
+        write!(f, "{}{}", self.prefix, self.override_)
+    }
+}
+
 fn continent(input: &str) -> PResult<Continent> {
-    alt((
-        map(tag("NA"), |_| Continent::NorthAmerica),
-        map(tag("SA"), |_| Continent::SouthAmerica),
-        map(tag("EU"), |_| Continent::Europe),
-        map(tag("AS"), |_| Continent::Asia),
-        map(tag("AF"), |_| Continent::Africa),
-        map(tag("OC"), |_| Continent::Oceania),
-    ))(input)
+    field(
+        Field::Continent,
+        alt((
+            map(tag("NA"), |_| Continent::NorthAmerica),
+            map(tag("SA"), |_| Continent::SouthAmerica),
+            map(tag("EU"), |_| Continent::Europe),
+            map(tag("AS"), |_| Continent::Asia),
+            map(tag("AF"), |_| Continent::Africa),
+            map(tag("OC"), |_| Continent::Oceania),
+        )),
+    )(input)
 }
 
 fn colon(input: &str) -> PResult<&str> {
-    tag(":")(input)
+    field(Field::Delimiter(':'), tag(":"))(input)
 }
 
 fn not_colon(input: &str) -> PResult<&str> {
@@ -84,13 +235,19 @@ fn not_colon(input: &str) -> PResult<&str> {
 }
 
 fn country_name(input: &str) -> PResult<&str> {
-    verify(not_colon, |country: &str| {
-        !country.starts_with(' ') && !country.starts_with('\t')
-    })(input)
+    field(
+        Field::CountryName,
+        verify(not_colon, |country: &str| {
+            !country.starts_with(' ') && !country.starts_with('\t')
+        }),
+    )(input)
 }
 
 fn main_prefix(input: &str) -> PResult<(bool, &str)> {
-    pair(map(opt(tag("*")), |s| s.is_some()), not_colon)(input)
+    field(
+        Field::MainPrefix,
+        pair(map(opt(tag("*")), |s| s.is_some()), not_colon),
+    )(input)
 }
 
 pub fn country_line(input: &str) -> PResult<CountryLine> {
@@ -100,9 +257,9 @@ pub fn country_line(input: &str) -> PResult<CountryLine> {
             delimited(space0, cq_zone, colon),
             delimited(space0, itu_zone, colon),
             delimited(space0, continent, colon),
-            delimited(space0, number::complete::float, colon), // lat
-            delimited(space0, number::complete::float, colon), // lon
-            delimited(space0, number::complete::float, colon), // "timezone"
+            delimited(space0, field(Field::Latitude, number::complete::float), colon),
+            delimited(space0, field(Field::Longitude, number::complete::float), colon),
+            delimited(space0, field(Field::Timezone, number::complete::float), colon),
             delimited(space0, main_prefix, colon),
         )),
         |(name, cq_zone, itu_zone, continent, lat, lon, timezone, (starred, main_prefix))| {
@@ -122,15 +279,18 @@ pub fn country_line(input: &str) -> PResult<CountryLine> {
 }
 
 fn prefix_string(input: &str) -> PResult<&str> {
-    recognize(fold_many1(none_of(",;()[]<>{}~"), || (), |_, _| ()))(input)
+    field(
+        Field::MainPrefix,
+        recognize(fold_many1(none_of(",;()[]<>{}~"), || (), |_, _| ())),
+    )(input)
 }
 
 fn cq_zone(input: &str) -> PResult<CqZone> {
-    map(character::complete::u8, CqZone)(input)
+    field(Field::CqZone, map(character::complete::u8, CqZone))(input)
 }
 
 fn itu_zone(input: &str) -> PResult<ItuZone> {
-    map(character::complete::u8, ItuZone)(input)
+    field(Field::ItuZone, map(character::complete::u8, ItuZone))(input)
 }
 
 fn prefix_override(input: &str) -> PResult<PrefixOverride> {
@@ -202,8 +362,8 @@ pub fn prefix_line(input: &str) -> PResult<Vec<Prefix>> {
 pub fn raw_prefix_line(input: &str) -> PResult<Vec<Prefix>> {
     map(
             pair(
-                many0(terminated(prefix, tag(","))),
-                opt(terminated(prefix, tag(";"))),
+                many0(terminated(prefix, field(Field::Delimiter(','), tag(",")))),
+                opt(terminated(prefix, field(Field::Delimiter(';'), tag(";")))),
             ),
         |(mut start, end)| {
             if let Some(end) = end {
@@ -222,6 +382,50 @@ pub fn line(input: &str) -> PResult<Line> {
     ))(input)
 }
 
+/// Async counterpart to `parse_reader`, driving the identical `all_consuming(line)`
+/// grammar over a `tokio::io::AsyncBufRead` instead of a blocking `Read`, so a
+/// caller can feed it bytes as they arrive (e.g. from a network download)
+/// rather than buffering the whole file first.
+///
+/// `consumer` takes an async callback rather than returning a `Stream`: each
+/// line is parsed into a single reused buffer, so a `Stream<Item = Line>`
+/// borrowing from it could only ever yield one line at a time anyway: the
+/// callback form makes that explicit instead of fighting the borrow checker
+/// over it.
+#[cfg(feature = "async")]
+pub async fn parse_reader_async<E, R, C, Fut>(
+    mut reader: R,
+    mut consumer: C,
+    max_line_length: usize,
+) -> Result<(), E>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+    E: From<std::io::Error>,
+    C: FnMut(Result<Line, CtyParseError>) -> Fut,
+    Fut: std::future::Future<Output = Result<(), E>>,
+{
+    use tokio::io::AsyncBufReadExt;
+
+    let mut buf = String::with_capacity(max_line_length);
+    let mut line_no = 0usize;
+
+    loop {
+        buf.clear();
+        if reader.read_line(&mut buf).await.map_err(E::from)? == 0 {
+            break;
+        }
+        line_no += 1;
+
+        let input_line = buf.trim_end_matches(|c| c == '\r' || c == '\n');
+        let result = all_consuming(line)(input_line)
+            .map(|(_, line)| line)
+            .map_err(|err| finalize_error(err, input_line, line_no));
+        consumer(result).await?;
+    }
+
+    Ok(())
+}
+
 pub fn parse_reader<E, R: Read, C>(
     reader: R,
     mut consumer: C,
@@ -229,15 +433,66 @@ pub fn parse_reader<E, R: Read, C>(
 ) -> Result<(), E>
 where
     E: From<std::io::Error>,
-    C: FnMut(PResult<Line>) -> Result<(), E>,
+    C: FnMut(Result<Line, CtyParseError>) -> Result<(), E>,
 {
     let mut reader = LineReader::with_capacity(max_line_length, reader);
+    let mut line_no = 0usize;
 
     while let Some(input_line) = reader.next_line() {
+        line_no += 1;
         let input_line = String::from_utf8_lossy(input_line?);
         let input_line = input_line.trim_end_matches(|c| c == '\r' || c == '\n');
-        consumer(all_consuming(line)(input_line))?;
+        let result = all_consuming(line)(input_line)
+            .map(|(_, line)| line)
+            .map_err(|err| finalize_error(err, input_line, line_no));
+        consumer(result)?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_country_roundtrip(line: &str) {
+        let (_, parsed) = all_consuming(country_line)(line).unwrap();
+        let written = parsed.to_string();
+        let (_, reparsed) = all_consuming(country_line)(written.as_str()).unwrap();
+
+        assert_eq!(parsed.name, reparsed.name);
+        assert_eq!(parsed.main_prefix, reparsed.main_prefix);
+        assert_eq!(u8::from(parsed.cq_zone), u8::from(reparsed.cq_zone));
+        assert_eq!(u8::from(parsed.itu_zone), u8::from(reparsed.itu_zone));
+        assert_eq!(parsed.lat, reparsed.lat);
+        assert_eq!(parsed.lon, reparsed.lon);
+        assert_eq!(parsed.timezone, reparsed.timezone);
+        assert_eq!(parsed.starred, reparsed.starred);
+    }
+
+    #[test]
+    fn test_country_line_roundtrip() {
+        assert_country_roundtrip("Czech Republic:15:28:EU:50.08:14.43:-1:*OK:");
+        assert_country_roundtrip("United States:5:8:NA:40.71:-74:5:K:");
+    }
+
+    #[test]
+    fn test_prefix_line_roundtrip() {
+        let line = "=K1ABC(14)[8]<40/-74>{NA}~5~,W1XYZ;";
+        let (_, parsed) = all_consuming(raw_prefix_line)(line).unwrap();
+        let written: String = parsed
+            .iter()
+            .enumerate()
+            .map(|(i, p)| format!("{p}{}", if i + 1 == parsed.len() { ';' } else { ',' }))
+            .collect();
+        let (_, reparsed) = all_consuming(raw_prefix_line)(written.as_str()).unwrap();
+
+        assert_eq!(parsed.len(), reparsed.len());
+        for (p, r) in parsed.iter().zip(reparsed.iter()) {
+            assert_eq!(p.exact, r.exact);
+            assert_eq!(p.prefix, r.prefix);
+            assert_eq!(p.override_.coordinates, r.override_.coordinates);
+            assert_eq!(p.override_.timezone, r.override_.timezone);
+        }
+    }
+}
@@ -1,13 +1,234 @@
-use std::io::{Cursor, Write};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
 use std::net::{
-    Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs, UdpSocket,
+    Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, TcpStream, ToSocketAddrs,
+    UdpSocket,
 };
-use std::sync::atomic::{AtomicI8, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI8, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use oneshot;
+
+/// Where keyer datagrams actually go: the local `cwdaemon` over UDP (the classic
+/// setup), or a TCP stream for operating a station remotely. A stdio/pipe
+/// transport is a natural next addition and would slot in here.
+pub(crate) enum Writer {
+    Udp(UdpSocket, SocketAddr),
+    Tcp(TcpStream),
+}
+
+impl Writer {
+    fn send(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        match self {
+            Writer::Udp(socket, dest) => socket.send_to(buf, *dest).map(|_| ()),
+            Writer::Tcp(stream) => stream.write_all(buf),
+        }
+    }
+}
+
+/// Read side of a keyer transport, used to pick up cwdaemon reply datagrams.
+pub(crate) enum Reader {
+    Udp(UdpSocket),
+    Tcp(Mutex<TcpStream>),
+}
+
+impl Reader {
+    /// Reads one chunk into `buf`, blocking for at most `timeout`. Used by the
+    /// cwdaemon reply receiver to interleave "did a reply arrive" with "is it
+    /// time to expire something", rather than blocking forever on one or the
+    /// other.
+    fn recv(&self, buf: &mut [u8], timeout: Duration) -> std::io::Result<usize> {
+        match self {
+            Reader::Udp(socket) => {
+                socket.set_read_timeout(Some(timeout))?;
+                socket.recv_from(buf).map(|(len, _from)| len)
+            }
+            Reader::Tcp(stream) => {
+                let mut stream = stream.lock().unwrap();
+                stream.set_read_timeout(Some(timeout))?;
+                stream.read(buf)
+            }
+        }
+    }
+}
+
+/// Selects which `Writer`/`Reader` pair `Netkeyer::connect` builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TransportKind {
+    Udp,
+    Tcp,
+}
+
+/// A symmetric stream cipher applied to outgoing keyer traffic. Intended for the
+/// TCP remote-operation transport, where keying/speed commands would otherwise go
+/// out in clear text; a no-op for the local UDP cwdaemon link.
+pub(crate) enum Cipher {
+    None,
+    Xor(Vec<u8>),
+}
+
+impl Cipher {
+    fn apply(&self, offset: usize, data: &mut [u8]) {
+        if let Cipher::Xor(key) = self {
+            if !key.is_empty() {
+                for (i, byte) in data.iter_mut().enumerate() {
+                    *byte ^= key[(offset + i) % key.len()];
+                }
+            }
+        }
+    }
+}
+
+/// Enough information to rebuild a `Netkeyer`'s transport from scratch: used
+/// by `Netkeyer::reconnect` to recover from a send failure, or from the
+/// cwdaemon host's address having changed since the last resolution.
+struct Endpoint {
+    kind: TransportKind,
+    host: String,
+    port: u16,
+}
+
+impl Endpoint {
+    fn resolve(&self) -> Result<SocketAddr, Error> {
+        Ok((self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))?)
+    }
+
+    fn build_writer(&self) -> Result<Writer, Error> {
+        let dest_addr = self.resolve()?;
+
+        Ok(match self.kind {
+            TransportKind::Udp => Writer::Udp(UdpSocket::bind(udp_bind_addr(dest_addr))?, dest_addr),
+            TransportKind::Tcp => Writer::Tcp(TcpStream::connect(dest_addr)?),
+        })
+    }
+}
 
 pub(crate) struct Netkeyer {
-    socket: UdpSocket,
-    dest_addr: SocketAddr,
+    writer: Mutex<Writer>,
+    endpoint: Endpoint,
+    cipher: Cipher,
+    write_offset: AtomicUsize,
     sc_volume: AtomicI8,
+    reply_seq: AtomicU64,
+    /// `None` until the first confirming send, or after `reconnect` has
+    /// rebuilt the transport and invalidated whatever reader the previous
+    /// receiver thread was reading from.
+    pending_replies: Mutex<Option<Arc<PendingReplies>>>,
+}
+
+/// Outcome of a `send_text_with_reply` request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyStatus {
+    /// cwdaemon echoed the reply token back before the deadline: everything
+    /// queued ahead of it, including the text this request sent, has gone out.
+    Confirmed,
+    /// No reply arrived before the deadline. cwdaemon may still be mid-flight;
+    /// this just means we stopped waiting so the caller doesn't block forever.
+    Unconfirmed,
+}
+
+struct PendingReply {
+    deadline: Instant,
+    sender: oneshot::Sender<ReplyStatus>,
+}
+
+/// FIFO queue of outstanding `send_text_with_reply` requests, one per
+/// `ESC 'h'` token sent to cwdaemon. cwdaemon processes queued commands
+/// strictly in order, so a reply always corresponds to the oldest pending
+/// request; there's no need to parse the echoed token back out.
+#[derive(Default)]
+struct PendingReplies {
+    queue: Mutex<VecDeque<PendingReply>>,
+    /// Set by `reconnect` before it drops this generation's `PendingReplies`,
+    /// so `spawn_reply_receiver`'s thread notices (within one `POLL_INTERVAL`)
+    /// that its `Reader` is about to be orphaned and exits instead of
+    /// polling a socket nobody will ever write to again.
+    shutdown: AtomicBool,
+}
+
+impl PendingReplies {
+    fn push(&self, deadline: Instant, sender: oneshot::Sender<ReplyStatus>) {
+        self.queue.lock().unwrap().push_back(PendingReply { deadline, sender });
+    }
+
+    /// Drops everything whose deadline has already passed, resolving it
+    /// "unconfirmed" so the caller isn't left hanging.
+    fn expire(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        let now = Instant::now();
+
+        while matches!(queue.front(), Some(pending) if pending.deadline <= now) {
+            let pending = queue.pop_front().unwrap();
+            let _ = pending.sender.send(ReplyStatus::Unconfirmed);
+        }
+    }
+
+    /// A reply datagram came in: expire anything that timed out ahead of it,
+    /// then resolve the oldest survivor as confirmed.
+    fn resolve_oldest(&self) {
+        self.expire();
+
+        if let Some(pending) = self.queue.lock().unwrap().pop_front() {
+            let _ = pending.sender.send(ReplyStatus::Confirmed);
+        }
+    }
+
+    /// Signals `spawn_reply_receiver`'s thread to stop polling its `Reader`.
+    fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Runs on a dedicated thread for the lifetime of the reply-tracking feature:
+/// reads whatever cwdaemon sends back on `reader`, treats any line starting
+/// with `h` as a reply to an `ESC 'h'` request, and resolves pending requests
+/// in FIFO order. Ticks on a short timeout even with no data so `expire` runs
+/// and unconfirmed requests don't wait forever for a reply that never comes.
+fn spawn_reply_receiver(reader: Reader, pending: Arc<PendingReplies>) {
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    std::thread::Builder::new()
+        .name("netkeyer-reply".to_owned())
+        .spawn(move || {
+            let mut buf = [0u8; 256];
+            let mut line = Vec::new();
+
+            loop {
+                if pending.shutdown.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                match reader.recv(&mut buf, POLL_INTERVAL) {
+                    Ok(0) => break,
+                    Ok(n) => line.extend_from_slice(&buf[..n]),
+                    Err(e)
+                        if e.kind() == std::io::ErrorKind::WouldBlock
+                            || e.kind() == std::io::ErrorKind::TimedOut => {}
+                    Err(_) => break,
+                }
+
+                pending.expire();
+
+                loop {
+                    let mut cursor = Cursor::new(&line);
+                    let Ok(reply) = cursor.get_until(b'\n') else {
+                        break;
+                    };
+                    let is_reply = reply.first() == Some(&b'h');
+                    let consumed = cursor.position();
+
+                    line.drain(..consumed);
+                    if is_reply {
+                        pending.resolve_oldest();
+                    }
+                }
+            }
+        })
+        .expect("spawn netkeyer-reply thread");
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -20,58 +241,222 @@ pub enum Error {
     InvalidDevice,
 }
 
+/// Retry-with-backoff policy for `Netkeyer::send_confirmed`. A send or reply
+/// timeout is tried up to `attempts` times in all, reconnecting the transport
+/// between attempts, with the delay between attempts growing by
+/// `backoff_factor` each time.
+pub(crate) struct RetryPolicy {
+    pub(crate) attempts: u32,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) backoff_factor: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            backoff_factor: 4,
+        }
+    }
+}
+
 const ESC: u8 = 0x1b;
 
-fn make_buf<const N: usize>() -> Cursor<[u8; N]> {
-    Cursor::new([0; N])
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub(crate) enum CursorError {
+    #[error("buffer overflow")]
+    BufferOverflow,
+}
+
+/// A write cursor over a caller-owned fixed-size buffer, used to compose one
+/// or more cwdaemon escape commands into a single datagram without
+/// allocating per command.
+pub(crate) struct CursorMut<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> CursorMut<'a> {
+    pub(crate) fn new(buf: &'a mut [u8]) -> Self {
+        CursorMut { buf, pos: 0 }
+    }
+
+    pub(crate) fn put_u8(&mut self, byte: u8) -> Result<(), CursorError> {
+        let dst = self
+            .buf
+            .get_mut(self.pos)
+            .ok_or(CursorError::BufferOverflow)?;
+        *dst = byte;
+        self.pos += 1;
+        Ok(())
+    }
+
+    pub(crate) fn put_str(&mut self, s: &str) -> Result<(), CursorError> {
+        s.bytes().try_for_each(|byte| self.put_u8(byte))
+    }
+
+    /// Emits an ESC-prefixed cwdaemon command: `0x1B`, `cmd`, then `param`
+    /// formatted as decimal text, the wire format every cwdaemon parameter
+    /// command shares.
+    pub(crate) fn put_esc(
+        &mut self,
+        cmd: u8,
+        param: impl std::fmt::Display,
+    ) -> Result<(), CursorError> {
+        self.put_u8(ESC)?;
+        self.put_u8(cmd)?;
+        write!(CursorWriter(self), "{param}").map_err(|_| CursorError::BufferOverflow)
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        &self.buf[..self.pos]
+    }
 }
 
-fn extract_buf<const N: usize>(cursor: &Cursor<[u8; N]>) -> &[u8] {
-    let s = cursor.get_ref().as_slice();
-    &s[..cursor.position() as usize]
+/// Adapts `CursorMut` to `std::fmt::Write` so `put_esc` can format its
+/// parameter in place instead of building a temporary `String`.
+struct CursorWriter<'a, 'b>(&'a mut CursorMut<'b>);
+
+impl std::fmt::Write for CursorWriter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0.put_str(s).map_err(|_| std::fmt::Error)
+    }
 }
 
-macro_rules! write_esc {
-    ($buf:expr,$fmt:expr,$value:expr) => {
-        write!($buf, concat!("\x1b", $fmt), $value).expect("buffer write errror");
-    };
+/// A read cursor over a reply datagram, used to pull out one or more
+/// `\r`/`\n`-terminated cwdaemon replies.
+pub(crate) struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
 }
 
-impl Netkeyer {
-    pub(crate) fn new(dest_addr: SocketAddr) -> Result<Netkeyer, Error> {
-        let bind_addr: SocketAddr = match dest_addr {
-            SocketAddr::V4(dest) => {
-                if dest.ip().is_loopback() {
-                    SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0).into()
-                } else {
-                    SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0).into()
-                }
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Cursor { buf, pos: 0 }
+    }
+
+    pub(crate) fn get_u8(&mut self) -> Result<u8, CursorError> {
+        let byte = *self
+            .buf
+            .get(self.pos)
+            .ok_or(CursorError::BufferOverflow)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Returns the bytes up to (but not including) the next `delim`,
+    /// advancing past it. `CursorError::BufferOverflow` if `delim` never
+    /// shows up, i.e. the datagram read so far doesn't contain a full reply.
+    pub(crate) fn get_until(&mut self, delim: u8) -> Result<&'a [u8], CursorError> {
+        let rest = &self.buf[self.pos..];
+        let len = rest
+            .iter()
+            .position(|&b| b == delim)
+            .ok_or(CursorError::BufferOverflow)?;
+
+        self.pos += len + 1;
+        Ok(&rest[..len])
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+fn udp_bind_addr(dest_addr: SocketAddr) -> SocketAddr {
+    match dest_addr {
+        SocketAddr::V4(dest) => {
+            if dest.ip().is_loopback() {
+                SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0).into()
+            } else {
+                SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0).into()
             }
-            SocketAddr::V6(dest) => {
-                if dest.ip().is_loopback() {
-                    SocketAddrV6::new(Ipv6Addr::LOCALHOST, 0, 0, 0).into()
-                } else {
-                    SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, 0, 0, 0).into()
-                }
+        }
+        SocketAddr::V6(dest) => {
+            if dest.ip().is_loopback() {
+                SocketAddrV6::new(Ipv6Addr::LOCALHOST, 0, 0, 0).into()
+            } else {
+                SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, 0, 0, 0).into()
             }
-        };
+        }
+    }
+}
+
+impl Netkeyer {
+    pub(crate) fn from_host_and_port(host: &str, port: u16) -> Result<Netkeyer, Error> {
+        Netkeyer::connect(TransportKind::Udp, host, port, Cipher::None)
+    }
 
-        let socket = UdpSocket::bind(bind_addr)?;
+    /// Builds a keyer over an explicit transport, optionally wrapped in a cipher;
+    /// used for remote operation over `TransportKind::Tcp`.
+    pub(crate) fn connect(kind: TransportKind, host: &str, port: u16, cipher: Cipher) -> Result<Netkeyer, Error> {
+        let endpoint = Endpoint {
+            kind,
+            host: host.to_owned(),
+            port,
+        };
+        let writer = endpoint.build_writer()?;
 
         Ok(Netkeyer {
-            socket,
-            dest_addr,
+            writer: Mutex::new(writer),
+            endpoint,
+            cipher,
+            write_offset: AtomicUsize::new(0),
             sc_volume: AtomicI8::new(-1),
+            reply_seq: AtomicU64::new(0),
+            pending_replies: Mutex::new(None),
         })
     }
 
-    pub(crate) fn from_host_and_port(host: &str, port: u16) -> Result<Netkeyer, Error> {
-        let dest_addr = (host, port)
-            .to_socket_addrs()?
-            .next()
-            .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))?;
+    /// The reader half matching this keyer's transport, used to listen for
+    /// cwdaemon reply datagrams.
+    pub(crate) fn try_clone_reader(&self) -> Result<Reader, Error> {
+        match &*self.writer.lock().unwrap() {
+            Writer::Udp(socket, _) => Ok(Reader::Udp(socket.try_clone()?)),
+            Writer::Tcp(stream) => Ok(Reader::Tcp(Mutex::new(stream.try_clone()?))),
+        }
+    }
 
-        Netkeyer::new(dest_addr)
+    /// Re-resolves the host address and rebinds/reconnects the transport in
+    /// place. Used by `send_confirmed` to recover from a send failure or
+    /// from the cwdaemon host's address having changed (e.g. a DHCP lease
+    /// renewal) since the last resolution. Any reply receiver thread that
+    /// was reading from the old transport is signalled to stop before it's
+    /// dropped; the cleared `pending_replies` makes the next confirming send
+    /// spin up a fresh one bound to the new transport instead.
+    fn reconnect(&self) -> Result<(), Error> {
+        let writer = self.endpoint.build_writer()?;
+        *self.writer.lock().unwrap() = writer;
+        if let Some(old) = self.pending_replies.lock().unwrap().take() {
+            old.shutdown();
+        }
+        Ok(())
+    }
+
+    /// Lazily starts the reply receiver thread on first use and returns the
+    /// shared queue it resolves against.
+    fn pending_replies(&self) -> Result<Arc<PendingReplies>, Error> {
+        let mut slot = self.pending_replies.lock().unwrap();
+
+        if let Some(pending) = slot.as_ref() {
+            return Ok(pending.clone());
+        }
+
+        let reader = self.try_clone_reader()?;
+        let pending = Arc::new(PendingReplies::default());
+        spawn_reply_receiver(reader, pending.clone());
+        *slot = Some(pending.clone());
+
+        Ok(pending)
+    }
+
+    fn send(&self, buf: &[u8]) -> Result<(), Error> {
+        let mut buf = buf.to_owned();
+        let offset = self.write_offset.fetch_add(buf.len(), Ordering::Relaxed);
+        self.cipher.apply(offset, &mut buf);
+        self.writer.lock().unwrap().send(&buf)?;
+        Ok(())
     }
 
     pub(crate) fn write_tone(&self, tone: u16) -> Result<(), Error> {
@@ -96,36 +481,83 @@ impl Netkeyer {
     #[inline]
     fn simple_command(&self, cmd: u8) -> Result<(), Error> {
         let cmd = [ESC, cmd];
-        let _ = self.socket.send_to(cmd.as_ref(), self.dest_addr)?;
-        Ok(())
+        self.send(cmd.as_ref())
+    }
+
+    /// Composes a single `ESC cmd param` command in an `N`-byte scratch
+    /// buffer and sends it. `N` needs to fit the two-byte `ESC cmd` prefix
+    /// plus `param`'s longest textual form; callers pick it from the
+    /// command's documented range.
+    fn send_esc<const N: usize>(&self, cmd: u8, param: impl std::fmt::Display) -> Result<(), Error> {
+        let mut raw = [0u8; N];
+        let mut cursor = CursorMut::new(&mut raw);
+
+        cursor
+            .put_esc(cmd, param)
+            .map_err(|_| Error::InvalidParameter)?;
+        self.send(cursor.as_slice())
     }
 
     pub(crate) fn reset(&self) -> Result<(), Error> {
         self.simple_command(b'0')
     }
 
-    pub(crate) fn set_speed(&self, speed: u8) -> Result<(), Error> {
-        let mut buf = make_buf::<4>();
+    /// Resets the keyer and applies any of `speed`/`weight`/`tone` that are
+    /// given, all as a single datagram instead of one round trip per
+    /// command. Used for the initial handshake, where several parameters get
+    /// set up front.
+    pub(crate) fn configure(
+        &self,
+        speed: Option<u8>,
+        weight: Option<i8>,
+        tone: Option<u16>,
+    ) -> Result<(), Error> {
+        if speed.is_some_and(|speed| !(5..=60).contains(&speed)) {
+            return Err(Error::InvalidParameter);
+        }
+        if weight.is_some_and(|weight| !(-50..=50).contains(&weight)) {
+            return Err(Error::InvalidParameter);
+        }
+        if tone.is_some_and(|tone| tone != 0 && !(300..=1000).contains(&tone)) {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut raw = [0u8; 32];
+        let mut cursor = CursorMut::new(&mut raw);
 
+        (|| -> Result<(), CursorError> {
+            cursor.put_u8(ESC)?;
+            cursor.put_u8(b'0')?;
+            if let Some(speed) = speed {
+                cursor.put_esc(b'2', speed)?;
+            }
+            if let Some(weight) = weight {
+                cursor.put_esc(b'7', weight)?;
+            }
+            if let Some(tone) = tone {
+                cursor.put_esc(b'3', tone)?;
+            }
+            Ok(())
+        })()
+        .map_err(|_| Error::InvalidParameter)?;
+
+        self.send(cursor.as_slice())
+    }
+
+    pub(crate) fn set_speed(&self, speed: u8) -> Result<(), Error> {
         if !(5..=60).contains(&speed) {
             return Err(Error::InvalidParameter);
         }
 
-        write_esc!(buf, "2{}", speed);
-        let _ = self.socket.send_to(extract_buf(&buf), self.dest_addr)?;
-        Ok(())
+        self.send_esc::<4>(b'2', speed)
     }
 
     pub(crate) fn set_tone(&self, tone: u16) -> Result<(), Error> {
-        let mut buf = make_buf::<6>();
-
         if tone != 0 && !(300..=1000).contains(&tone) {
             return Err(Error::InvalidParameter);
         }
 
-        write_esc!(buf, "3{}", tone);
-        let _ = self.socket.send_to(extract_buf(&buf), self.dest_addr)?;
-        Ok(())
+        self.send_esc::<6>(b'3', tone)
     }
 
     pub(crate) fn abort(&self) -> Result<(), Error> {
@@ -142,100 +574,84 @@ impl Netkeyer {
     }
 
     pub(crate) fn set_weight(&self, weight: i8) -> Result<(), Error> {
-        let mut buf = make_buf::<6>();
-
         if !(-50..=50).contains(&weight) {
             return Err(Error::InvalidParameter);
         }
 
-        write_esc!(buf, "7{}", weight);
-        let _ = self.socket.send_to(extract_buf(&buf), self.dest_addr)?;
-        Ok(())
+        self.send_esc::<6>(b'7', weight)
     }
 
     pub(crate) fn set_device(&self, device: &[u8]) -> Result<(), Error> {
-        let mut buf = Vec::with_capacity(device.len() + 2);
-        buf.push(ESC);
-        buf.push(b'8');
-        buf.extend_from_slice(device);
+        let mut raw = vec![0u8; device.len() + 2];
+        let mut cursor = CursorMut::new(&mut raw);
 
-        let _ = self.socket.send_to(&buf, self.dest_addr)?;
-        Ok(())
+        cursor
+            .put_u8(ESC)
+            .and_then(|_| cursor.put_u8(b'8'))
+            .and_then(|_| device.iter().try_for_each(|&byte| cursor.put_u8(byte)))
+            .map_err(|_| Error::InvalidParameter)?;
+
+        self.send(cursor.as_slice())
     }
 
     pub(crate) fn set_ptt(&self, ptt: bool) -> Result<(), Error> {
-        let mut buf = make_buf::<3>();
-        write_esc!(buf, "a{}", ptt as u8);
-        let _ = self.socket.send_to(extract_buf(&buf), self.dest_addr)?;
-        Ok(())
+        self.send_esc::<3>(b'a', ptt as u8)
     }
 
     pub(crate) fn set_pin14(&self, pin14: bool) -> Result<(), Error> {
-        let mut buf = make_buf::<3>();
-        write_esc!(buf, "b{}", pin14 as u8);
-        let _ = self.socket.send_to(extract_buf(&buf), self.dest_addr)?;
-        Ok(())
+        self.send_esc::<3>(b'b', pin14 as u8)
     }
 
     pub(crate) fn tune(&self, seconds: u8) -> Result<(), Error> {
-        let mut buf = make_buf::<4>();
-
         if seconds > 10 {
             return Err(Error::InvalidParameter);
         }
 
-        write_esc!(buf, "c{}", seconds);
-        let _ = self.socket.send_to(extract_buf(&buf), self.dest_addr)?;
-        Ok(())
+        self.send_esc::<4>(b'c', seconds)
     }
 
     pub(crate) fn set_tx_delay(&self, ms: u8) -> Result<(), Error> {
-        let mut buf = make_buf::<4>();
-
         if ms > 50 {
             return Err(Error::InvalidParameter);
         }
 
-        write_esc!(buf, "d{}", ms);
-        let _ = self.socket.send_to(extract_buf(&buf), self.dest_addr)?;
-        Ok(())
+        self.send_esc::<4>(b'd', ms)
     }
 
     pub(crate) fn set_band_switch(&self, bandindex: u8) -> Result<(), Error> {
-        let mut buf = make_buf::<4>();
-
         if !(1..=9).contains(&bandindex) {
             return Err(Error::InvalidParameter);
         }
 
-        write_esc!(buf, "e{}", bandindex);
-        let _ = self.socket.send_to(extract_buf(&buf), self.dest_addr)?;
-        Ok(())
+        self.send_esc::<4>(b'e', bandindex)
     }
 
     pub(crate) fn set_sidetone_device(&self, dev: u8) -> Result<(), Error> {
-        let cmd = [ESC, b'f', dev];
-
         if !b"coapns".contains(&dev) {
             return Err(Error::InvalidParameter);
         }
 
-        let _ = self.socket.send_to(cmd.as_ref(), self.dest_addr)?;
-        Ok(())
+        let mut raw = [0u8; 3];
+        let mut cursor = CursorMut::new(&mut raw);
+
+        cursor
+            .put_u8(ESC)
+            .and_then(|_| cursor.put_u8(b'f'))
+            .and_then(|_| cursor.put_u8(dev))
+            .map_err(|_| Error::InvalidParameter)?;
+
+        self.send(cursor.as_slice())
     }
 
     pub(crate) fn set_sidetone_volume(&self, volume: u8) -> Result<(), Error> {
-        self.sc_volume
-            .store(volume.try_into().ok().unwrap_or(-1), Ordering::Release);
-        let mut buf = make_buf::<6>();
-
         if volume > 100 {
             return Err(Error::InvalidParameter);
         }
 
-        write_esc!(buf, "g{}", volume);
-        let _ = self.socket.send_to(extract_buf(&buf), self.dest_addr)?;
-        Ok(())
+        self.sc_volume
+            .store(volume.try_into().ok().unwrap_or(-1), Ordering::Release);
+
+        self.send_esc::<6>(b'g', volume)
     }
 
     pub(crate) fn send_text(&self, text: &[u8]) -> Result<(), Error> {
@@ -243,7 +659,82 @@ impl Netkeyer {
             return Err(Error::InvalidParameter);
         }
 
-        let _ = self.socket.send_to(text, self.dest_addr)?;
-        Ok(())
+        self.send(text)
+    }
+
+    /// Sends an `ESC 'h'` request with a fresh token, which cwdaemon echoes
+    /// back as `h<token>\r\n` once everything queued ahead of it, including
+    /// whatever was just sent, has actually been keyed. The returned
+    /// receiver yields `ReplyStatus::Confirmed` when that arrives, or
+    /// `Unconfirmed` if nothing does within `timeout`.
+    fn with_reply(&self, timeout: Duration) -> Result<oneshot::Receiver<ReplyStatus>, Error> {
+        let pending = self.pending_replies()?;
+        let token = self.reply_seq.fetch_add(1, Ordering::Relaxed).to_string();
+
+        let mut raw = vec![0u8; token.len() + 2];
+        let mut cursor = CursorMut::new(&mut raw);
+
+        cursor
+            .put_u8(ESC)
+            .and_then(|_| cursor.put_u8(b'h'))
+            .and_then(|_| cursor.put_str(&token))
+            .map_err(|_| Error::InvalidParameter)?;
+        self.send(cursor.as_slice())?;
+
+        let (sender, receiver) = oneshot::channel();
+        pending.push(Instant::now() + timeout, sender);
+
+        Ok(receiver)
+    }
+
+    /// Like `send_text`, but also asks cwdaemon to confirm once everything
+    /// queued up to and including this message has actually been keyed.
+    pub(crate) fn send_text_with_reply(
+        &self,
+        text: &[u8],
+        timeout: Duration,
+    ) -> Result<oneshot::Receiver<ReplyStatus>, Error> {
+        self.send_text(text)?;
+        self.with_reply(timeout)
+    }
+
+    /// Confirming, auto-reconnecting counterpart to the plain command
+    /// methods above: runs `f` against this keyer, then waits up to
+    /// `timeout` for cwdaemon to confirm everything `f` sent has actually
+    /// been applied. On failure (a send error or an unconfirmed reply),
+    /// reconnects (re-resolving the host and rebinding the transport) and
+    /// retries, making at most `policy.attempts` attempts in all before
+    /// giving up.
+    pub(crate) fn send_confirmed<F: Fn(&Netkeyer) -> Result<(), Error>>(
+        &self,
+        policy: &RetryPolicy,
+        timeout: Duration,
+        f: F,
+    ) -> Result<(), Error> {
+        let mut delay = policy.initial_backoff;
+        let mut last_err = Error::InvalidDevice;
+
+        for attempt in 0..policy.attempts.max(1) {
+            let outcome = f(self).and_then(|_| self.with_reply(timeout)).and_then(|receiver| {
+                match receiver.recv() {
+                    Ok(ReplyStatus::Confirmed) => Ok(()),
+                    _ => Err(Error::InvalidDevice),
+                }
+            });
+
+            match outcome {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    last_err = e;
+                    if attempt + 1 < policy.attempts {
+                        let _ = self.reconnect();
+                        std::thread::sleep(delay);
+                        delay *= policy.backoff_factor;
+                    }
+                }
+            }
+        }
+
+        Err(last_err)
     }
 }
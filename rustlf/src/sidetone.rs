@@ -0,0 +1,234 @@
+//! Local CW sidetone synthesis.
+//!
+//! Renders Morse text as actual audio on the local sound card through a small
+//! phase-accumulator oscillator, so e.g. the CQWW simulator can be heard without
+//! driving a rig or keyer.
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    sync::{Arc, Mutex},
+};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use crate::err_utils::{log_message, LogLevel};
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("no default output device")]
+    NoDevice,
+    #[error("could not read device configuration: {0}")]
+    DeviceConfig(#[from] cpal::DefaultStreamConfigError),
+    #[error("could not build output stream: {0}")]
+    BuildStream(#[from] cpal::BuildStreamError),
+    #[error("could not start output stream: {0}")]
+    PlayStream(#[from] cpal::PlayStreamError),
+}
+
+/// A key-down or key-up element of a given length, in samples.
+struct Segment {
+    tone_on: bool,
+    samples_remaining: u32,
+}
+
+struct Oscillator {
+    phase: f32,
+}
+
+impl Oscillator {
+    fn next(&mut self, freq: f32, sample_rate: f32) -> f32 {
+        let sample = self.phase.sin();
+        self.phase += 2.0 * PI * freq / sample_rate;
+        if self.phase >= 2.0 * PI {
+            self.phase -= 2.0 * PI;
+        }
+        sample
+    }
+}
+
+/// Raised-cosine key-click envelope applied at both ends of every segment.
+const ENVELOPE_SAMPLES: u32 = 200;
+
+struct SynthState {
+    queue: VecDeque<Segment>,
+    current: Option<Segment>,
+    elapsed_in_current: u32,
+    osc: Oscillator,
+    freq: f32,
+    sample_rate: f32,
+}
+
+impl SynthState {
+    fn envelope(position: u32, length: u32) -> f32 {
+        if position >= length {
+            1.0
+        } else {
+            0.5 - 0.5 * (PI * position as f32 / length as f32).cos()
+        }
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        loop {
+            match &self.current {
+                Some(seg) if seg.samples_remaining > 0 => break,
+                _ => {
+                    self.current = self.queue.pop_front();
+                    self.elapsed_in_current = 0;
+                    if self.current.is_none() {
+                        return 0.0;
+                    }
+                }
+            }
+        }
+
+        let seg = self.current.as_mut().unwrap();
+        seg.samples_remaining -= 1;
+        self.elapsed_in_current += 1;
+
+        let raw = if seg.tone_on {
+            self.osc.next(self.freq, self.sample_rate)
+        } else {
+            0.0
+        };
+
+        let ramp_in = Self::envelope(self.elapsed_in_current, ENVELOPE_SAMPLES);
+        let ramp_out = Self::envelope(seg.samples_remaining, ENVELOPE_SAMPLES);
+        raw * ramp_in.min(ramp_out)
+    }
+}
+
+/// A persistent output stream that keys Morse text fed to it through `key`.
+pub(crate) struct SidetoneGenerator {
+    state: Arc<Mutex<SynthState>>,
+    // Kept alive for as long as the generator exists; never read directly.
+    _stream: cpal::Stream,
+}
+
+impl SidetoneGenerator {
+    pub(crate) fn new() -> Result<SidetoneGenerator, Error> {
+        let host = cpal::default_host();
+        let device = host.default_output_device().ok_or(Error::NoDevice)?;
+        let supported = device.default_output_config()?;
+        let sample_rate = supported.sample_rate().0 as f32;
+        let channels = supported.channels();
+        let config: cpal::StreamConfig = supported.into();
+
+        let state = Arc::new(Mutex::new(SynthState {
+            queue: VecDeque::new(),
+            current: None,
+            elapsed_in_current: 0,
+            osc: Oscillator { phase: 0.0 },
+            freq: 600.0,
+            sample_rate,
+        }));
+
+        let cb_state = state.clone();
+        let stream = device.build_output_stream(
+            &config,
+            move |data: &mut [f32], _| {
+                let mut state = cb_state.lock().unwrap();
+                for frame in data.chunks_mut(channels as usize) {
+                    let sample = state.next_sample();
+                    frame.fill(sample);
+                }
+            },
+            |err| log_message!(LogLevel::WARN, format!("sidetone stream error: {err}")),
+            None,
+        )?;
+        stream.play()?;
+
+        Ok(SidetoneGenerator {
+            state,
+            _stream: stream,
+        })
+    }
+
+    /// Queues `text` as Morse at `freq` Hz, timed for `wpm` words per minute.
+    pub(crate) fn key(&self, text: &str, freq: u16, wpm: u32) {
+        let sample_rate = self.state.lock().unwrap().sample_rate;
+        let dot_secs = 1.2 / wpm.max(1) as f32;
+        let to_samples = |secs: f32| (secs * sample_rate) as u32;
+        let element = |tone_on: bool, secs: f32| Segment {
+            tone_on,
+            samples_remaining: to_samples(secs),
+        };
+
+        let mut segments = Vec::new();
+        for (word_idx, word) in text.split(' ').filter(|w| !w.is_empty()).enumerate() {
+            if word_idx > 0 {
+                segments.push(element(false, dot_secs * 7.0));
+            }
+
+            for (char_idx, c) in word.chars().enumerate() {
+                let Some(pattern) = morse_pattern(c) else {
+                    continue;
+                };
+                if char_idx > 0 {
+                    segments.push(element(false, dot_secs * 3.0));
+                }
+
+                for (elem_idx, elem) in pattern.chars().enumerate() {
+                    if elem_idx > 0 {
+                        segments.push(element(false, dot_secs));
+                    }
+                    segments.push(element(true, if elem == '-' { dot_secs * 3.0 } else { dot_secs }));
+                }
+            }
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.freq = freq as f32;
+        state.queue.extend(segments);
+    }
+
+    /// Silences any queued or in-progress element.
+    pub(crate) fn stop(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.queue.clear();
+        state.current = None;
+    }
+}
+
+fn morse_pattern(c: char) -> Option<&'static str> {
+    Some(match c.to_ascii_uppercase() {
+        'A' => ".-",
+        'B' => "-...",
+        'C' => "-.-.",
+        'D' => "-..",
+        'E' => ".",
+        'F' => "..-.",
+        'G' => "--.",
+        'H' => "....",
+        'I' => "..",
+        'J' => ".---",
+        'K' => "-.-",
+        'L' => ".-..",
+        'M' => "--",
+        'N' => "-.",
+        'O' => "---",
+        'P' => ".--.",
+        'Q' => "--.-",
+        'R' => ".-.",
+        'S' => "...",
+        'T' => "-",
+        'U' => "..-",
+        'V' => "...-",
+        'W' => ".--",
+        'X' => "-..-",
+        'Y' => "-.--",
+        'Z' => "--..",
+        '0' => "-----",
+        '1' => ".----",
+        '2' => "..---",
+        '3' => "...--",
+        '4' => "....-",
+        '5' => ".....",
+        '6' => "-....",
+        '7' => "--...",
+        '8' => "---..",
+        '9' => "----.",
+        '/' => "-..-.",
+        '?' => "..--..",
+        _ => return None,
+    })
+}
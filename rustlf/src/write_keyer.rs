@@ -49,7 +49,7 @@ pub unsafe extern "C" fn keyer_append(text: *const c_char) {
 }
 
 #[inline]
-fn keyer_append_safe(mut text: &[u8]) {
+pub(crate) fn keyer_append_safe(mut text: &[u8]) {
     let mut producer = KEYER_PRODUCER.lock().unwrap();
     let producer = producer.as_mut().expect("Keyer queue not initialized");
 